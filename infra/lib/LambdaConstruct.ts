@@ -32,7 +32,8 @@ export class LambdaConstruct extends Construct {
         path.join(__dirname, '../../service/target/lambda/bootstrap'),
       ),
       memorySize: 256,
-      timeout: Duration.seconds(10),
+      // Long-polling reads wait up to 20 seconds for a game to change.
+      timeout: Duration.seconds(30),
       environment: {
         RUST_LOG: 'info',
       },
@@ -51,6 +52,8 @@ export class LambdaConstruct extends Construct {
           HttpMethod.DELETE,
         ],
         allowedHeaders: ['*'],
+        // The app sends a game's ETag back in If-Match, so it has to be able to read it.
+        exposedHeaders: ['ETag'],
       },
     });
   }
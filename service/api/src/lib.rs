@@ -0,0 +1,990 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use codenames_common::{
+    game::{
+        model::{Game, GameData, GameState, Player},
+        service::GameService,
+    },
+    ClueBody, DailyGameBody, DictionaryListBody, DictionaryReportBody, ErrorBody, GameListBody,
+    GameNameBody, LeaderboardBody, NewGameBody, ReadyBody, ServiceError, SpymasterHandOffBody,
+    SwitchPlayerBody,
+};
+use http::{
+    header::{AUTHORIZATION, ETAG, IF_MATCH, IF_NONE_MATCH},
+    Method, Request, Response, StatusCode,
+};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+
+pub mod socket;
+
+/// The longest a long-polling read may wait for a game to change, kept well below the 30 second
+/// timeout of the lambda function (`infra/lib/LambdaConstruct.ts`).
+const MAX_LONG_POLL_WAIT: Duration = Duration::from_secs(20);
+
+/// A request as seen by the router, independent of the server framework that received it.
+pub type ApiRequest = Request<Vec<u8>>;
+/// A response produced by the router, for the server framework to send back.
+pub type ApiResponse = Response<String>;
+
+fn error_response(status: StatusCode, body: &ErrorBody) -> ApiResponse {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(serde_json::to_string(body).unwrap())
+        .unwrap()
+}
+
+fn json_response<T: Serialize>(value: &T) -> ApiResponse {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/json")
+        .body(serde_json::to_string(value).unwrap())
+        .unwrap()
+}
+
+pub fn service_error_response(err: &ServiceError) -> ApiResponse {
+    let status = match err {
+        ServiceError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        ServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+        ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+        ServiceError::Conflict(_) => StatusCode::CONFLICT,
+        ServiceError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ServiceError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+        ServiceError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_response(status, &err.body())
+}
+
+fn not_modified_response() -> ApiResponse {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .body(String::new())
+        .unwrap()
+}
+
+fn respond<T: Serialize>(result: &Result<T, ServiceError>) -> ApiResponse {
+    match result {
+        Ok(value) => json_response(value),
+        Err(err) => service_error_response(err),
+    }
+}
+
+/// A game response that can be cached by the client: its version changes on every save, and its
+/// role tells the spymaster's projection apart from everyone else's.
+trait Tagged: Serialize {
+    fn version(&self) -> u64;
+    fn role(&self) -> &'static str;
+
+    fn etag(&self) -> String {
+        format!("\"{}-{}\"", self.version(), self.role())
+    }
+}
+
+impl Tagged for GameState {
+    fn version(&self) -> u64 {
+        self.info.version()
+    }
+
+    fn role(&self) -> &'static str {
+        "state"
+    }
+}
+
+impl Tagged for GameData {
+    fn version(&self) -> u64 {
+        self.info.version()
+    }
+
+    fn role(&self) -> &'static str {
+        "data"
+    }
+}
+
+impl Tagged for Game {
+    fn version(&self) -> u64 {
+        match self {
+            Game::State(state) => state.version(),
+            Game::Data(data) => data.version(),
+        }
+    }
+
+    fn role(&self) -> &'static str {
+        match self {
+            Game::State(state) => state.role(),
+            Game::Data(data) => data.role(),
+        }
+    }
+}
+
+/// Like `respond`, with an `ETag` header, and 304 Not Modified for a GET whose `If-None-Match`
+/// already names it.
+fn respond_tagged<T: Tagged>(req: &ApiRequest, result: &Result<T, ServiceError>) -> ApiResponse {
+    match result {
+        Ok(game) => {
+            let etag = game.etag();
+            let not_modified = req.method() == Method::GET
+                && header(req, IF_NONE_MATCH.as_str())
+                    .map(|tags| entity_tags(&tags).any(|tag| tag == "*" || tag == etag))
+                    .unwrap_or(false);
+            let mut response = if not_modified {
+                not_modified_response()
+            } else {
+                json_response(game)
+            };
+            response.headers_mut().insert(ETAG, etag.parse().unwrap());
+            response
+        }
+        Err(err) => service_error_response(err),
+    }
+}
+
+fn header(req: &ApiRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// Splits an `If-Match` or `If-None-Match` header into its entity tags, ignoring weak prefixes.
+fn entity_tags(header: &str) -> impl Iterator<Item = &str> {
+    header
+        .split(',')
+        .map(|tag| tag.trim())
+        .map(|tag| tag.strip_prefix("W/").unwrap_or(tag))
+}
+
+/// Rejects a mutation made with `If-Match` against a game that has been saved since the client
+/// fetched it. Only the version is compared, so either projection's tag can be sent back. Returns
+/// the version the save must still find, so a change made in the meantime is caught as well.
+async fn check_if_match(
+    req: &ApiRequest,
+    mut service: GameService,
+    id: &str,
+) -> Result<Option<u64>, ServiceError> {
+    let tags = match header(req, IF_MATCH.as_str()) {
+        None => return Ok(None),
+        Some(tags) => tags,
+    };
+    if entity_tags(&tags).any(|tag| tag == "*") {
+        return Ok(None);
+    }
+    let version = service.get(id, &None, &None).await?.version();
+    let current = entity_tags(&tags).any(|tag| {
+        tag.trim_matches('"')
+            .split_once('-')
+            .and_then(|(v, _)| v.parse::<u64>().ok())
+            == Some(version)
+    });
+    if current {
+        Ok(Some(version))
+    } else {
+        Err(GameService::stale_game(version))
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(req: &ApiRequest) -> Result<T, ServiceError> {
+    Ok(serde_json::from_slice(req.body().as_ref())?)
+}
+
+/// Routes a request to the matching `GameService` operation.
+pub async fn router(req: ApiRequest, service: GameService) -> ApiResponse {
+    let path = req.uri().path().to_string();
+    let method = req.method().clone();
+
+    // Strip trailing slash for consistency
+    let path = path.trim_end_matches('/');
+
+    // Split path into segments and URL-decode them
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+    let service = match (&method, segments.as_slice()) {
+        (&Method::PUT, ["game", id, _, ..]) => {
+            match check_if_match(&req, service.clone(), id).await {
+                Ok(Some(version)) => service.with_expected_version(version),
+                Ok(None) => service,
+                Err(e) => return service_error_response(&e),
+            }
+        }
+        _ => service,
+    };
+
+    match (method, segments.as_slice()) {
+        // GET / — random game name
+        (Method::GET, []) => {
+            let result = service.random_name().await.map(GameNameBody::new);
+            respond(&result)
+        }
+
+        // GET /dictionaries — list the dictionaries games can draw words from
+        (Method::GET, ["dictionaries"]) => {
+            json_response(&DictionaryListBody::new(service.dictionaries()))
+        }
+
+        // POST /dictionaries/reload — load the dictionaries again from their source, for admins
+        // sending `Authorization: Bearer {secret}`
+        (Method::POST, ["dictionaries", "reload"]) => {
+            let secret = header(&req, AUTHORIZATION.as_str());
+            let secret = secret
+                .as_deref()
+                .and_then(|value| value.strip_prefix("Bearer "));
+            let result = service
+                .reload_dictionaries(secret)
+                .await
+                .map(DictionaryReportBody::new);
+            respond(&result)
+        }
+
+        // POST /daily — start a game on the board of the day
+        (Method::POST, ["daily"]) => {
+            let body: DailyGameBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service.new_daily_game(body.game_name).await;
+            respond_tagged(&req, &result)
+        }
+
+        // GET /daily/leaderboard?date={yyyy-mm-dd} — results of a day's challenge, today by default
+        (Method::GET, ["daily", "leaderboard"]) => {
+            let date = match extract_query_param(&req, "date") {
+                None => Utc::today().naive_utc(),
+                Some(date) => match date.parse::<NaiveDate>() {
+                    Ok(d) => d,
+                    Err(_) => {
+                        return service_error_response(&ServiceError::BadRequest(format!(
+                            "invalid date: {}",
+                            date
+                        )));
+                    }
+                },
+            };
+            let result = service
+                .daily_leaderboard(date)
+                .await
+                .map(|results| LeaderboardBody::new(date, results));
+            respond(&result)
+        }
+
+        // GET /game — list all games
+        (Method::GET, ["game"]) => {
+            let result = service.clone().find().await.map(GameListBody::new);
+            respond(&result)
+        }
+
+        // POST /game — create new game
+        (Method::POST, ["game"]) => {
+            let body: NewGameBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service
+                .new_game(body.game_name, body.settings, body.custom_words, body.seed)
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // GET /game/{id} — get game state
+        (Method::GET, ["game", id]) => get_game(&req, service, id, None, None).await,
+
+        // PUT /game/{id}/join — join game
+        (Method::PUT, ["game", id, "join"]) => {
+            let player: Player = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service.join(id.to_string(), player).await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/end-turn — end turn
+        (Method::PUT, ["game", id, "end-turn"]) => {
+            let result = service.end_turn(id.to_string()).await;
+            respond_tagged(&req, &result)
+        }
+
+        // GET /game/{id}/replay/{step} — the game as it was after a number of events
+        (Method::GET, ["game", id, "replay", step]) => {
+            let step: usize = match step.parse() {
+                Ok(s) => s,
+                Err(_) => {
+                    return service_error_response(&ServiceError::BadRequest(
+                        "invalid step".into(),
+                    ));
+                }
+            };
+            let player_name = extract_query_param(&req, "player");
+            let secret = extract_query_param(&req, "secret");
+            let result = service
+                .clone()
+                .replay(id, step, &player_name, &secret)
+                .await;
+            respond(&result)
+        }
+
+        // GET /game/{id}/{player} — get player game view
+        (Method::GET, ["game", id, player_name]) => {
+            let secret = extract_query_param(&req, "secret");
+            get_game(&req, service, id, Some(player_name.to_string()), secret).await
+        }
+
+        // PUT /game/{id}/{player}/start-turn — spymaster starts turn
+        (Method::PUT, ["game", id, player_name, "start-turn"]) => {
+            let clue: ClueBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service
+                .start_turn(
+                    id.to_string(),
+                    player_name.to_string(),
+                    (clue.word, clue.amount),
+                )
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/guess/{index} — operative guesses
+        (Method::PUT, ["game", id, player_name, "guess", index]) => {
+            let board_index: usize = match index.parse() {
+                Ok(i) => i,
+                Err(_) => {
+                    return service_error_response(&ServiceError::BadRequest(
+                        "invalid index".into(),
+                    ));
+                }
+            };
+            let result = service
+                .guess(id.to_string(), (player_name, board_index))
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/ready — mark ready (or not) in the lobby
+        (Method::PUT, ["game", id, player_name, "ready"]) => {
+            let body: ReadyBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service
+                .set_ready(id.to_string(), player_name, body.ready)
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/start — host ends the lobby phase
+        (Method::PUT, ["game", id, player_name, "start"]) => {
+            let result = service.start_game(id.to_string(), player_name).await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/switch — change team and/or role
+        (Method::PUT, ["game", id, player_name, "switch"]) => {
+            let body: SwitchPlayerBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service
+                .switch_player(
+                    id.to_string(),
+                    player_name,
+                    body.team,
+                    body.spymaster_secret,
+                )
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/hand-off — spymaster hands the role to a teammate
+        (Method::PUT, ["game", id, player_name, "hand-off"]) => {
+            let body: SpymasterHandOffBody = match parse_body(&req) {
+                Ok(b) => b,
+                Err(e) => return service_error_response(&e),
+            };
+            let result = service
+                .hand_off_spymaster(
+                    id.to_string(),
+                    player_name,
+                    &body.teammate,
+                    body.spymaster_secret,
+                )
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/reroll/{index} — replace a card's word before the first clue
+        (Method::PUT, ["game", id, player_name, "reroll", index]) => {
+            let board_index: usize = match index.parse() {
+                Ok(i) => i,
+                Err(_) => {
+                    return service_error_response(&ServiceError::BadRequest(
+                        "invalid index".into(),
+                    ));
+                }
+            };
+            let result = service
+                .reroll(id.to_string(), player_name, board_index)
+                .await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/undo — take back the latest clue or guess
+        (Method::PUT, ["game", id, player_name, "undo"]) => {
+            let result = service.undo(id.to_string(), player_name).await;
+            respond_tagged(&req, &result)
+        }
+
+        // PUT /game/{id}/{player}/leave — leave game
+        (Method::PUT, ["game", id, player_name, "leave"]) => {
+            let result = service.leave(id.to_string(), player_name).await;
+            respond_tagged(&req, &result)
+        }
+
+        _ => service_error_response(&ServiceError::NotFound(ErrorBody::new(
+            "NOT_FOUND",
+            "not found".into(),
+        ))),
+    }
+}
+
+/// Reads a game for a viewer. With `?since={version}` the game is only returned once its version is
+/// newer, waiting up to `&wait={seconds}` for that, and otherwise the response is 304 Not Modified.
+async fn get_game(
+    req: &ApiRequest,
+    mut service: GameService,
+    id: &str,
+    player_name: Option<String>,
+    secret: Option<String>,
+) -> ApiResponse {
+    let since = match parse_since(req) {
+        Ok(since) => since,
+        Err(e) => return service_error_response(&e),
+    };
+    match since {
+        None => respond_tagged(req, &service.get(id, &player_name, &secret).await),
+        Some((version, wait)) => {
+            match service
+                .get_since(id, &player_name, &secret, version, wait)
+                .await
+            {
+                Ok(None) => not_modified_response(),
+                Ok(Some(game)) => respond_tagged(req, &Ok(game)),
+                Err(e) => service_error_response(&e),
+            }
+        }
+    }
+}
+
+fn parse_since(req: &ApiRequest) -> Result<Option<(u64, Duration)>, ServiceError> {
+    let version = match extract_query_param(req, "since") {
+        None => return Ok(None),
+        Some(since) => since
+            .parse::<u64>()
+            .map_err(|_| ServiceError::BadRequest(format!("invalid since: {}", since)))?,
+    };
+    let wait = match extract_query_param(req, "wait") {
+        None => Duration::ZERO,
+        Some(wait) => Duration::from_secs(
+            wait.parse::<u64>()
+                .map_err(|_| ServiceError::BadRequest(format!("invalid wait: {}", wait)))?,
+        ),
+    };
+    Ok(Some((version, wait.min(MAX_LONG_POLL_WAIT))))
+}
+
+fn extract_query_param(req: &ApiRequest, key: &str) -> Option<String> {
+    req.uri().query().and_then(|q| {
+        q.split('&')
+            .filter_map(|pair| {
+                let (k, v) = pair.split_once('=')?;
+                if k == key {
+                    Some(v.to_string())
+                } else {
+                    None
+                }
+            })
+            .next()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use codenames_common::{
+        dictionary::WordGeneratorRand,
+        game::{
+            board::BoardGeneratorRand,
+            dao::MemoryDao,
+            model::{CardColor, Game, GameSettings, Player, Team},
+            service::GameService,
+        },
+        ServiceError,
+    };
+    use http::{
+        header::{AUTHORIZATION, ETAG, IF_MATCH, IF_NONE_MATCH},
+        Method, Request, StatusCode,
+    };
+
+    use crate::{router, ApiRequest};
+
+    fn test_service() -> GameService {
+        GameService::new(
+            Box::new(WordGeneratorRand),
+            Box::new(BoardGeneratorRand),
+            Box::new(MemoryDao::new()),
+        )
+        .unwrap()
+    }
+
+    fn request(method: Method, uri: &str, body: &str) -> ApiRequest {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(body.as_bytes().to_vec())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_and_get_game() {
+        let service = test_service();
+        let created = router(
+            request(Method::POST, "/game", r#"{"game_name": "foo-bar"}"#),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::OK);
+
+        let fetched = router(request(Method::GET, "/game/FOO-BAR/", ""), service.clone()).await;
+        assert_eq!(fetched.status(), StatusCode::OK);
+        assert!(fetched.body().contains(r#""name":"foo-bar""#));
+
+        let untimed = router(
+            request(
+                Method::POST,
+                "/game",
+                r#"{"game_name": "baz", "settings": {"guess_time_limit": 0}}"#,
+            ),
+            service,
+        )
+        .await;
+        assert_eq!(untimed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(untimed.body().contains(r#""code":"INVALID_SETTINGS""#));
+    }
+
+    #[tokio::test]
+    async fn get_game_since() {
+        let service = test_service();
+        router(
+            request(Method::POST, "/game", r#"{"game_name": "foo"}"#),
+            service.clone(),
+        )
+        .await;
+
+        let unchanged = router(
+            request(Method::GET, "/game/foo?since=1", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+
+        let changed = router(
+            request(Method::GET, "/game/foo?since=0", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(changed.status(), StatusCode::OK);
+        assert!(changed.body().contains(r#""version":1"#));
+
+        let waiting = tokio::spawn(router(
+            request(Method::GET, "/game/foo?since=1&wait=5", ""),
+            service.clone(),
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        router(
+            request(
+                Method::PUT,
+                "/game/foo/join",
+                r#"{"name": "bar", "team": "Red"}"#,
+            ),
+            service,
+        )
+        .await;
+        let updated = waiting.await.unwrap();
+        assert_eq!(updated.status(), StatusCode::OK);
+        assert!(updated.body().contains(r#""version":2"#));
+    }
+
+    #[tokio::test]
+    async fn conditional_requests() {
+        let service = test_service();
+        router(
+            request(Method::POST, "/game", r#"{"game_name": "foo"}"#),
+            service.clone(),
+        )
+        .await;
+
+        let fetched = router(request(Method::GET, "/game/foo", ""), service.clone()).await;
+        let etag = fetched.headers()[ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, r#""1-state""#);
+
+        let mut cached = request(Method::GET, "/game/foo", "");
+        cached
+            .headers_mut()
+            .insert(IF_NONE_MATCH, etag.parse().unwrap());
+        let cached = router(cached, service.clone()).await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.body(), "");
+
+        let join = |etag: &str| {
+            let mut req = request(
+                Method::PUT,
+                "/game/foo/join",
+                r#"{"name": "bar", "team": "Red"}"#,
+            );
+            req.headers_mut().insert(IF_MATCH, etag.parse().unwrap());
+            router(req, service.clone())
+        };
+        let joined = join(&etag).await;
+        assert_eq!(joined.status(), StatusCode::OK);
+        assert_eq!(joined.headers()[ETAG], r#""2-state""#);
+
+        let stale = join(&etag).await;
+        assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
+        assert!(stale.body().contains(r#""code":"STALE_GAME""#));
+    }
+
+    #[tokio::test]
+    async fn concurrent_conditional_requests() {
+        let service = test_service();
+        service
+            .new_game("foo".to_string(), GameSettings::default(), None, None)
+            .await
+            .unwrap();
+        let player = |name: &str| Player {
+            team: Team::Red,
+            name: name.to_string(),
+            spymaster_secret: None,
+        };
+
+        // Both clients pass the `If-Match` check against version 1 before either saves.
+        let first = service.clone().with_expected_version(1);
+        let second = service.clone().with_expected_version(1);
+        assert!(first.join("foo".to_string(), player("bar")).await.is_ok());
+        match second.join("foo".to_string(), player("baz")).await {
+            Err(ServiceError::PreconditionFailed(body)) => {
+                assert_eq!(body.code, "STALE_GAME");
+                assert_eq!(body.details["version"], 2);
+            }
+            result => panic!("expected a stale game, got {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn replay() {
+        let service = test_service();
+        router(
+            request(Method::POST, "/game", r#"{"game_name": "foo"}"#),
+            service.clone(),
+        )
+        .await;
+        router(
+            request(
+                Method::PUT,
+                "/game/foo/join",
+                r#"{"name": "bar", "team": "Red"}"#,
+            ),
+            service.clone(),
+        )
+        .await;
+
+        let created = router(
+            request(Method::GET, "/game/foo/replay/0", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::OK);
+        assert!(created.body().contains(r#""players":{}"#));
+
+        let joined = router(
+            request(Method::GET, "/game/foo/replay/1", ""),
+            service.clone(),
+        )
+        .await;
+        assert!(joined.body().contains(r#""name":"bar""#));
+
+        router(
+            request(
+                Method::PUT,
+                "/game/foo/join",
+                r#"{"name": "sam", "team": "Blue", "spymaster_secret": "s"}"#,
+            ),
+            service.clone(),
+        )
+        .await;
+        let before_joining = router(
+            request(Method::GET, "/game/foo/replay/1?player=sam&secret=s", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(before_joining.status(), StatusCode::OK);
+        assert!(before_joining.body().contains(r#""type":"State""#));
+        assert!(!before_joining.body().contains(r#""name":"sam""#));
+
+        let stranger = router(
+            request(Method::GET, "/game/foo/replay/1?player=nobody", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(stranger.status(), StatusCode::NOT_FOUND);
+
+        let missing = router(request(Method::GET, "/game/foo/replay/3", ""), service).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn seeded_boards() {
+        let service = test_service();
+        let spymaster_view = |name: &'static str| {
+            let service = service.clone();
+            async move {
+                let body = serde_json::json!({ "game_name": name, "seed": 42 });
+                let created = router(
+                    request(Method::POST, "/game", &body.to_string()),
+                    service.clone(),
+                )
+                .await;
+                assert!(!created.body().contains("seed"));
+                router(
+                    request(
+                        Method::PUT,
+                        &format!("/game/{}/join", name),
+                        r#"{"name": "sam", "team": "Red", "spymaster_secret": "s"}"#,
+                    ),
+                    service.clone(),
+                )
+                .await;
+                let response = router(
+                    request(Method::GET, &format!("/game/{}/sam?secret=s", name), ""),
+                    service,
+                )
+                .await;
+                serde_json::from_str::<serde_json::Value>(response.body()).unwrap()
+            }
+        };
+
+        let first = spymaster_view("first").await;
+        let second = spymaster_view("second").await;
+        assert_eq!(first["seed"], 42);
+        assert_eq!(first["board"], second["board"]);
+        assert_eq!(first["turns"], second["turns"]);
+    }
+
+    #[tokio::test]
+    async fn daily_challenge() {
+        let unconfigured = router(
+            request(Method::POST, "/daily", r#"{"game_name": "first"}"#),
+            test_service(),
+        )
+        .await;
+        assert_eq!(unconfigured.status(), StatusCode::NOT_FOUND);
+
+        let service = test_service().with_daily_key("secret".to_string());
+        let board = |name: &'static str| {
+            let service = service.clone();
+            async move {
+                let body = format!(r#"{{"game_name": "{}"}}"#, name);
+                let response = router(request(Method::POST, "/daily", &body), service).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                serde_json::from_str::<serde_json::Value>(response.body()).unwrap()["board"].clone()
+            }
+        };
+        assert_eq!(board("first").await, board("second").await);
+
+        let leaderboard = router(
+            request(Method::GET, "/daily/leaderboard?date=2024-02-29", ""),
+            service.clone(),
+        )
+        .await;
+        assert_eq!(leaderboard.status(), StatusCode::OK);
+        assert_eq!(leaderboard.body(), r#"{"date":"2024-02-29","results":[]}"#);
+
+        let invalid = router(
+            request(Method::GET, "/daily/leaderboard?date=yesterday", ""),
+            service,
+        )
+        .await;
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn daily_result_recorded_once() {
+        let service = test_service().with_daily_key("secret".to_string());
+        service.new_daily_game("daily".to_string()).await.unwrap();
+        let players = [
+            ("blue-spy", Team::Blue, true),
+            ("blue-op", Team::Blue, false),
+            ("red-spy", Team::Red, true),
+            ("red-op", Team::Red, false),
+        ];
+        for &(name, team, spymaster) in players.iter() {
+            let player = Player {
+                team,
+                name: name.to_string(),
+                spymaster_secret: if spymaster { Some("s".into()) } else { None },
+            };
+            service.join("daily".to_string(), player).await.unwrap();
+        }
+
+        let game = match service
+            .clone()
+            .get("daily", &Some("blue-spy".into()), &Some("s".into()))
+            .await
+            .unwrap()
+        {
+            Game::Data(game) => game,
+            game => panic!("expected the spymaster view, got {:?}", game),
+        };
+        assert_eq!(game.info.settings().reroll_limit, 0);
+        let (spymaster, operative) = match game.info.current_turn().team() {
+            Team::Blue => ("blue-spy", "blue-op"),
+            Team::Red => ("red-spy", "red-op"),
+        };
+        let death = game
+            .board
+            .iter()
+            .position(|card| card.color == CardColor::Death)
+            .unwrap();
+        service
+            .start_turn(
+                "daily".to_string(),
+                spymaster.to_string(),
+                ("clue".to_string(), 1),
+            )
+            .await
+            .unwrap();
+        let lose = || service.guess("daily".to_string(), (operative, death));
+
+        assert!(lose().await.is_ok());
+        let undo = service.undo("daily".to_string(), operative).await;
+        assert_eq!(undo.err().unwrap().body().code, "RESULT_RECORDED");
+        assert!(lose().await.is_err());
+
+        let results = service
+            .daily_leaderboard(Utc::today().naive_utc())
+            .await
+            .unwrap();
+        let mut teams: Vec<Team> = results.iter().map(|result| result.team).collect();
+        teams.sort_by_key(|team| team.to_string());
+        assert_eq!(teams, vec![Team::Blue, Team::Red]);
+    }
+
+    #[tokio::test]
+    async fn custom_words() {
+        let service = test_service();
+        let words =
+            |count: usize| -> Vec<String> { (0..count).map(|i| format!("jargon-{}", i)).collect() };
+        let create = |name: &str, custom_words: serde_json::Value| {
+            let body = serde_json::json!({ "game_name": name, "custom_words": custom_words });
+            router(
+                request(Method::POST, "/game", &body.to_string()),
+                service.clone(),
+            )
+        };
+
+        let board = create(
+            "board",
+            serde_json::json!({ "type": "Board", "words": words(25) }),
+        )
+        .await;
+        assert_eq!(board.status(), StatusCode::OK);
+        assert!(board.body().contains(r#""word":"jargon-24""#));
+        assert!(board.body().contains(r#""reroll_limit":0"#));
+        router(
+            request(
+                Method::PUT,
+                "/game/board/join",
+                r#"{"name": "sam", "team": "Red"}"#,
+            ),
+            service.clone(),
+        )
+        .await;
+        let reroll = router(
+            request(Method::PUT, "/game/board/sam/reroll/0", ""),
+            service.clone(),
+        )
+        .await;
+        assert!(reroll.body().contains(r#""code":"REROLL_LIMIT""#));
+
+        let pool = create(
+            "pool",
+            serde_json::json!({ "type": "Pool", "words": words(30), "save_as": "Team" }),
+        )
+        .await;
+        assert_eq!(pool.status(), StatusCode::OK);
+
+        let taken = create(
+            "taken",
+            serde_json::json!({ "type": "Pool", "words": words(26), "save_as": "TEAM" }),
+        )
+        .await;
+        assert_eq!(taken.status(), StatusCode::CONFLICT);
+
+        let saved = create(
+            "saved",
+            serde_json::json!({ "type": "Saved", "name": "team" }),
+        )
+        .await;
+        assert_eq!(saved.status(), StatusCode::OK);
+        assert_eq!(saved.body().matches(r#""word":"jargon-"#).count(), 25);
+
+        let too_few = create(
+            "few",
+            serde_json::json!({ "type": "Board", "words": words(24) }),
+        )
+        .await;
+        assert_eq!(too_few.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(too_few.body().contains(r#""code":"INVALID_WORD_LIST""#));
+    }
+
+    #[tokio::test]
+    async fn reload_dictionaries() {
+        let reload = |service: GameService, secret: Option<&str>| {
+            let mut req = request(Method::POST, "/dictionaries/reload", "");
+            if let Some(secret) = secret {
+                req.headers_mut()
+                    .insert(AUTHORIZATION, format!("Bearer {}", secret).parse().unwrap());
+            }
+            router(req, service)
+        };
+
+        let unconfigured = reload(test_service(), Some("admin")).await;
+        assert_eq!(unconfigured.status(), StatusCode::NOT_FOUND);
+
+        let service = test_service().with_admin_secret("admin".to_string());
+        let anonymous = reload(service.clone(), None).await;
+        assert_eq!(anonymous.status(), StatusCode::FORBIDDEN);
+        let wrong = reload(service.clone(), Some("guess")).await;
+        assert_eq!(wrong.status(), StatusCode::FORBIDDEN);
+        let admin = reload(service, Some("admin")).await;
+        assert_eq!(admin.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn error_body() {
+        let service = test_service();
+        let response = router(request(Method::GET, "/game/missing", ""), service.clone()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.body().contains(r#""code":"NOT_FOUND""#));
+
+        let response = router(request(Method::POST, "/game", "{"), service).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,239 @@
+use codenames_common::{
+    game::{
+        model::{Game, Player, Team},
+        service::GameService,
+    },
+    ErrorBody, ServiceError, ServiceResult,
+};
+use serde::{Deserialize, Serialize};
+
+/// A command sent by a client over a game socket, on behalf of the player the socket belongs to.
+/// The optional `id` is echoed back in the `Ack` or `Error` reply.
+#[derive(Debug, Deserialize)]
+pub struct ClientMessage {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum Command {
+    Join {
+        team: Team,
+        #[serde(default)]
+        spymaster_secret: Option<String>,
+    },
+    Ready {
+        ready: bool,
+    },
+    Start,
+    Switch {
+        team: Team,
+        #[serde(default)]
+        spymaster_secret: Option<String>,
+    },
+    Leave,
+    Clue {
+        word: String,
+        amount: usize,
+    },
+    Guess {
+        index: usize,
+    },
+    EndTurn,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Ack {
+        id: Option<String>,
+    },
+    Error {
+        id: Option<String>,
+        error: ErrorBody,
+    },
+    Game {
+        game: Box<Game>,
+    },
+}
+
+/// Parses a client message and applies its command to the game. The updated game itself is not
+/// part of the reply; it reaches every connected client through the broadcast of saved games.
+pub async fn handle_message(
+    service: &GameService,
+    key: &str,
+    player_name: &str,
+    text: &str,
+) -> ServerMessage {
+    let ClientMessage { id, command } = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return ServerMessage::Error {
+                id: None,
+                error: ServiceError::from(e).body(),
+            }
+        }
+    };
+    match apply(service, key, player_name, command).await {
+        Ok(()) => ServerMessage::Ack { id },
+        Err(e) => ServerMessage::Error {
+            id,
+            error: e.body(),
+        },
+    }
+}
+
+async fn apply(
+    service: &GameService,
+    key: &str,
+    player_name: &str,
+    command: Command,
+) -> ServiceResult<()> {
+    let key = key.to_string();
+    match command {
+        Command::Join {
+            team,
+            spymaster_secret,
+        } => {
+            let player = Player {
+                team,
+                name: player_name.to_string(),
+                spymaster_secret,
+            };
+            service.join(key, player).await.map(|_| ())
+        }
+        Command::Ready { ready } => service.set_ready(key, player_name, ready).await.map(|_| ()),
+        Command::Start => service.start_game(key, player_name).await.map(|_| ()),
+        Command::Switch {
+            team,
+            spymaster_secret,
+        } => service
+            .switch_player(key, player_name, team, spymaster_secret)
+            .await
+            .map(|_| ()),
+        Command::Leave => service.leave(key, player_name).await.map(|_| ()),
+        Command::Clue { word, amount } => service
+            .start_turn(key, player_name.to_string(), (word, amount))
+            .await
+            .map(|_| ()),
+        Command::Guess { index } => service.guess(key, (player_name, index)).await.map(|_| ()),
+        Command::EndTurn => service.end_turn(key).await.map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codenames_common::{
+        dictionary::WordGeneratorRand,
+        game::{
+            board::BoardGeneratorRand,
+            dao::MemoryDao,
+            model::{Game, GameSettings, Team},
+            service::GameService,
+        },
+    };
+
+    use crate::socket::{handle_message, ServerMessage};
+
+    async fn test_service() -> GameService {
+        let service = GameService::new(
+            Box::new(WordGeneratorRand),
+            Box::new(BoardGeneratorRand),
+            Box::new(MemoryDao::new()),
+        )
+        .unwrap();
+        service
+            .new_game(
+                "foo".to_string(),
+                GameSettings {
+                    lobby: true,
+                    ..GameSettings::default()
+                },
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        service
+    }
+
+    #[tokio::test]
+    async fn commands() {
+        let service = test_service().await;
+
+        let reply = handle_message(
+            &service,
+            "foo",
+            "bar",
+            r#"{"id": "1", "type": "Join", "team": "Blue"}"#,
+        )
+        .await;
+        assert!(matches!(reply, ServerMessage::Ack { id: Some(id) } if id == "1"));
+
+        let reply =
+            handle_message(&service, "foo", "bar", r#"{"type": "Guess", "index": 0}"#).await;
+        assert!(
+            matches!(reply, ServerMessage::Error { id: None, error } if error.code == "GAME_NOT_STARTED")
+        );
+
+        let reply = handle_message(&service, "foo", "bar", r#"{"type": "Dance"}"#).await;
+        assert!(
+            matches!(reply, ServerMessage::Error { id: None, error } if error.code == "BAD_REQUEST")
+        );
+    }
+
+    #[tokio::test]
+    async fn lobby_to_first_guess() {
+        let service = test_service().await;
+        let send = |player: &'static str, text: &'static str| {
+            let service = service.clone();
+            async move {
+                let reply = handle_message(&service, "foo", player, text).await;
+                assert!(
+                    matches!(reply, ServerMessage::Ack { .. }),
+                    "{} {}: {:?}",
+                    player,
+                    text,
+                    reply
+                );
+            }
+        };
+
+        send("blue-spy", r#"{"type": "Join", "team": "Red"}"#).await;
+        send(
+            "blue-spy",
+            r#"{"type": "Switch", "team": "Blue", "spymaster_secret": "s"}"#,
+        )
+        .await;
+        send("blue-op", r#"{"type": "Join", "team": "Blue"}"#).await;
+        send(
+            "red-spy",
+            r#"{"type": "Join", "team": "Red", "spymaster_secret": "t"}"#,
+        )
+        .await;
+        send("red-op", r#"{"type": "Join", "team": "Red"}"#).await;
+        send("extra", r#"{"type": "Join", "team": "Red"}"#).await;
+        send("extra", r#"{"type": "Leave"}"#).await;
+        for player in ["blue-spy", "blue-op", "red-spy", "red-op"] {
+            send(player, r#"{"type": "Ready", "ready": true}"#).await;
+        }
+        send("blue-spy", r#"{"type": "Start"}"#).await;
+
+        let (spymaster, operative) = match service.clone().get("foo", &None, &None).await {
+            Ok(Game::State(state)) if state.info.current_turn().team() == &Team::Blue => {
+                ("blue-spy", "blue-op")
+            }
+            _ => ("red-spy", "red-op"),
+        };
+        send(spymaster, r#"{"type": "Clue", "word": "sea", "amount": 1}"#).await;
+        send(operative, r#"{"type": "Guess", "index": 0}"#).await;
+
+        match service.clone().get("foo", &None, &None).await.unwrap() {
+            Game::State(state) => assert_eq!(state.info.guesses().len(), 1),
+            game => panic!("expected the public view, got {:?}", game),
+        }
+    }
+}
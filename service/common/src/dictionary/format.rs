@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::model::{validate_word, MIN_WORD_POOL_SIZE},
+    Lowercase,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentRating {
+    General,
+    Mature,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordMetadata {
+    pub difficulty: Option<Difficulty>,
+    pub category: Option<String>,
+    pub rating: Option<ContentRating>,
+}
+
+/// The words of a dictionary with whatever is known about each of them.
+pub type Dictionary = HashMap<String, WordMetadata>;
+
+/// What was found while reading a dictionary. It is only used if there are no errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DictionaryReport {
+    pub name: String,
+    pub size: usize,
+    pub accepted: bool,
+    /// Words that appeared more than once, ignoring case; only the first is kept.
+    pub duplicates: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Reads a dictionary file: one word per line, optionally followed by `|`-separated metadata,
+/// e.g. `Ghost | difficulty=easy | category=halloween | rating=general`. Blank lines and lines
+/// starting with `#` are skipped, and both `\n` and `\r\n` line endings are accepted.
+pub fn parse_dictionary(name: &str, contents: &[u8]) -> (Dictionary, DictionaryReport) {
+    let mut dictionary = Dictionary::new();
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    let mut errors = Vec::new();
+
+    let text = match std::str::from_utf8(contents) {
+        Ok(text) => text.trim_start_matches('\u{feff}'),
+        Err(e) => {
+            errors.push(format!("not UTF-8: {}", e));
+            ""
+        }
+    };
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Ok((word, metadata)) => {
+                if seen.insert(Lowercase::new(word.as_str())) {
+                    dictionary.insert(word, metadata);
+                } else {
+                    duplicates.push(word);
+                }
+            }
+            Err(e) => errors.push(format!("line {}: {}", number + 1, e)),
+        }
+    }
+    if errors.is_empty() && dictionary.len() < MIN_WORD_POOL_SIZE {
+        errors.push(format!(
+            "needs at least {} words, has {}",
+            MIN_WORD_POOL_SIZE,
+            dictionary.len()
+        ));
+    }
+
+    let report = DictionaryReport {
+        name: name.to_string(),
+        size: dictionary.len(),
+        accepted: errors.is_empty(),
+        duplicates,
+        errors,
+    };
+    (dictionary, report)
+}
+
+fn parse_line(line: &str) -> Result<(String, WordMetadata), String> {
+    let mut fields = line.split('|').map(|f| f.trim());
+    let word = validate_word(fields.next().unwrap_or_default()).map_err(|e| e.to_string())?;
+
+    let mut metadata = WordMetadata::default();
+    for field in fields {
+        let (key, value) = field
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .ok_or_else(|| format!("expected key=value, got {:?}", field))?;
+        match key {
+            "difficulty" => metadata.difficulty = Some(parse_value(key, value)?),
+            "category" if !value.is_empty() => metadata.category = Some(value.to_lowercase()),
+            "rating" => metadata.rating = Some(parse_value(key, value)?),
+            _ => return Err(format!("unknown metadata {:?}", field)),
+        }
+    }
+    Ok((word, metadata))
+}
+
+fn parse_value<T: serde::de::DeserializeOwned>(key: &str, value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown {} {:?}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dictionary::{parse_dictionary, ContentRating, Difficulty, WordMetadata};
+
+    fn words(count: usize) -> String {
+        (0..count)
+            .map(|i| format!("Word{}\r\n", i))
+            .collect::<String>()
+    }
+
+    #[test]
+    fn normalizes_lines() {
+        let contents = format!(
+            "\u{feff}# animals\r\n{}\r\n  Tiger  | difficulty=Hard | category=Cats | rating=general\r\ntiger\n\n",
+            words(26)
+        );
+        let (dictionary, report) = parse_dictionary("test", contents.as_bytes());
+
+        assert!(report.accepted);
+        assert_eq!(report.size, 27);
+        assert_eq!(report.duplicates, vec!["tiger".to_string()]);
+        assert_eq!(dictionary["Word0"], WordMetadata::default());
+        assert_eq!(
+            dictionary["Tiger"],
+            WordMetadata {
+                difficulty: Some(Difficulty::Hard),
+                category: Some("cats".to_string()),
+                rating: Some(ContentRating::General),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_packs() {
+        let (_, report) = parse_dictionary("short", words(3).as_bytes());
+        assert!(!report.accepted);
+
+        let contents = format!(
+            "{}Tiger | difficulty=extreme\n{}\n",
+            words(26),
+            "x".repeat(30)
+        );
+        let (_, report) = parse_dictionary("bad", contents.as_bytes());
+        assert!(!report.accepted);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].starts_with("line 27: unknown difficulty"));
+    }
+}
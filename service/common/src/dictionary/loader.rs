@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use dyn_clone::DynClone;
+use log::debug;
+
+use crate::{dictionary::DictionaryType, game::dao::GameDao, StdResult};
+
+/// The contents of a dictionary file, before it is parsed and checked.
+#[derive(Debug, Clone)]
+pub struct DictionarySource {
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+/// Where `DictionaryService` gets its dictionaries from, at startup and on every reload.
+#[async_trait]
+pub trait DictionaryLoader: DynClone + Send + Sync {
+    async fn load(&self) -> StdResult<Vec<DictionarySource>>;
+}
+
+dyn_clone::clone_trait_object!(DictionaryLoader);
+
+/// The dictionaries compiled into the binary.
+#[derive(Clone)]
+pub struct EmbeddedDictionaries;
+
+#[async_trait]
+impl DictionaryLoader for EmbeddedDictionaries {
+    async fn load(&self) -> StdResult<Vec<DictionarySource>> {
+        Ok(embedded_sources())
+    }
+}
+
+pub fn embedded_sources() -> Vec<DictionarySource> {
+    DictionaryType::ALL.iter().map(|t| t.source()).collect()
+}
+
+/// Every `.txt` file in a directory, named after the file.
+#[derive(Clone)]
+pub struct DirectoryDictionaries {
+    path: PathBuf,
+}
+
+impl DirectoryDictionaries {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl DictionaryLoader for DirectoryDictionaries {
+    async fn load(&self) -> StdResult<Vec<DictionarySource>> {
+        debug!(
+            "call: dictionary.DirectoryDictionaries.load {:?}",
+            self.path
+        );
+        let mut sources = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = match (path.extension(), path.file_stem()) {
+                (Some(extension), Some(stem)) if extension == "txt" => {
+                    stem.to_string_lossy().to_lowercase()
+                }
+                _ => continue,
+            };
+            let contents = tokio::fs::read(&path).await?;
+            sources.push(DictionarySource { name, contents });
+        }
+        Ok(sources)
+    }
+}
+
+/// The dictionaries an admin keeps in the game store. Word pools saved by players are not among
+/// them.
+#[derive(Clone)]
+pub struct StoreDictionaries {
+    dao: Box<dyn GameDao>,
+}
+
+impl StoreDictionaries {
+    pub fn new(dao: Box<dyn GameDao>) -> Self {
+        Self { dao }
+    }
+}
+
+#[async_trait]
+impl DictionaryLoader for StoreDictionaries {
+    async fn load(&self) -> StdResult<Vec<DictionarySource>> {
+        Ok(self
+            .dao
+            .clone()
+            .dictionaries()
+            .await?
+            .into_iter()
+            .map(|(name, words)| DictionarySource {
+                name: name.value().to_string(),
+                contents: words.join("\n").into_bytes(),
+            })
+            .collect())
+    }
+}
+
+/// Picks a loader from `CODENAMES_DICTIONARIES`: `store` for the game store, a directory path,
+/// or the embedded dictionaries when unset.
+pub fn dictionary_loader_from_env(dao: Box<dyn GameDao>) -> Box<dyn DictionaryLoader> {
+    match std::env::var("CODENAMES_DICTIONARIES").ok().as_deref() {
+        None | Some("") | Some("embedded") => Box::new(EmbeddedDictionaries),
+        Some("store") => Box::new(StoreDictionaries::new(dao)),
+        Some(path) => Box::new(DirectoryDictionaries::new(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        dictionary::{
+            DictionaryService, DirectoryDictionaries, StoreDictionaries, WordGeneratorRand,
+            DEFAULT_DICTIONARY,
+        },
+        game::dao::{GameDao, MemoryDao},
+        Lowercase,
+    };
+
+    fn words(prefix: &str, count: usize) -> String {
+        (0..count)
+            .map(|i| format!("{}{}", prefix, i))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn sizes(service: &DictionaryService) -> Vec<(String, usize)> {
+        service
+            .dictionaries()
+            .into_iter()
+            .map(|d| (d.name, d.size))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn load_directory_and_reload() {
+        let dir =
+            std::env::temp_dir().join(format!("codenames-dictionaries-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Planets.txt"), words("Planet", 30)).unwrap();
+        fs::write(dir.join("short.txt"), words("Moon", 3)).unwrap();
+        fs::write(dir.join("notes.md"), "not a dictionary").unwrap();
+
+        let service = DictionaryService::new(Box::new(WordGeneratorRand))
+            .unwrap()
+            .with_loader(Box::new(DirectoryDictionaries::new(&dir)))
+            .await;
+        let clone = service.clone();
+        let names: Vec<String> = sizes(&service).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            vec!["animals", DEFAULT_DICTIONARY, "food", "planets", "spanish"]
+        );
+        assert_eq!(sizes(&service)[3], ("planets".to_string(), 30));
+
+        fs::remove_file(dir.join("Planets.txt")).unwrap();
+        fs::write(dir.join("planets.txt"), words("Planet", 26)).unwrap();
+        let reports = service.reload().await.unwrap();
+        assert_eq!(sizes(&clone)[3], ("planets".to_string(), 26));
+        assert!(!reports.iter().find(|r| r.name == "short").unwrap().accepted);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(service.reload().await.is_err());
+        assert_eq!(sizes(&clone)[3], ("planets".to_string(), 26));
+    }
+
+    #[tokio::test]
+    async fn load_store_without_word_pools() {
+        let mut dao = MemoryDao::new().with_dictionary(
+            "animals",
+            words("Animal", 30).lines().map(String::from).collect(),
+        );
+        let pool: Vec<String> = words("Pool", 30).lines().map(String::from).collect();
+        dao.set_word_pool(Lowercase::new(DEFAULT_DICTIONARY), pool)
+            .await
+            .unwrap();
+
+        let service = DictionaryService::new(Box::new(WordGeneratorRand))
+            .unwrap()
+            .with_loader(Box::new(StoreDictionaries::new(Box::new(dao))))
+            .await;
+        let embedded = DictionaryService::new(Box::new(WordGeneratorRand)).unwrap();
+        assert_eq!(sizes(&service)[0], ("animals".to_string(), 30));
+        assert_eq!(sizes(&service)[1..], sizes(&embedded)[1..]);
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_embedded() {
+        let service = DictionaryService::new(Box::new(WordGeneratorRand))
+            .unwrap()
+            .with_loader(Box::new(DirectoryDictionaries::new("/no/such/directory")))
+            .await;
+        assert_eq!(service.dictionaries().len(), 4);
+    }
+}
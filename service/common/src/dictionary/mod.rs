@@ -0,0 +1,665 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryInto,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use dyn_clone::DynClone;
+pub use format::*;
+pub use loader::*;
+use log::{debug, warn};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    game::model::{BoardDifficulty, BOARD_SIZE},
+    ErrorBody, ServiceError, ServiceResult, StdResult,
+};
+
+mod format;
+mod loader;
+
+pub type Dictionaries = HashMap<String, Dictionary>;
+
+#[derive(Clone)]
+pub struct DictionaryService {
+    /// Shared between clones, so a reload is seen by all of them.
+    dictionaries: Arc<RwLock<Dictionaries>>,
+    loader: Box<dyn DictionaryLoader>,
+    generator: Box<dyn WordGenerator>,
+}
+
+/// A dictionary's name and how many words it has.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DictionarySummary {
+    pub name: String,
+    pub size: usize,
+}
+
+impl DictionaryService {
+    pub fn new(generator: Box<dyn WordGenerator>) -> StdResult<DictionaryService> {
+        debug!("call: dictionary.Service::new");
+        let (dictionaries, reports) = check_dictionaries(embedded_sources());
+        if let Some(report) = reports.iter().find(|r| !r.accepted) {
+            return Err(format!("embedded dictionary rejected: {:?}", report).into());
+        }
+        Ok(DictionaryService {
+            dictionaries: Arc::new(RwLock::new(dictionaries)),
+            loader: Box::new(EmbeddedDictionaries),
+            generator,
+        })
+    }
+
+    /// Switches to another source of dictionaries and loads them. If they cannot be loaded the
+    /// embedded dictionaries stay in use.
+    pub async fn with_loader(self, loader: Box<dyn DictionaryLoader>) -> DictionaryService {
+        let service = DictionaryService { loader, ..self };
+        match service.reload().await {
+            Ok(reports) => reports
+                .iter()
+                .filter(|r| !r.accepted)
+                .for_each(|r| warn!("dictionary {} rejected: {:?}", r.name, r.errors)),
+            Err(e) => warn!(
+                "could not load dictionaries, using the embedded ones: {}",
+                e
+            ),
+        }
+        service
+    }
+
+    /// Loads the dictionaries again, keeping the current ones if that fails. Dictionaries with
+    /// errors are left out, and the embedded dictionaries are used under any name the source does
+    /// not have.
+    pub async fn reload(&self) -> ServiceResult<Vec<DictionaryReport>> {
+        let sources = self
+            .loader
+            .load()
+            .await
+            .map_err(|e| ServiceError::Unknown(e.to_string()))?;
+        let (mut loaded, reports) = check_dictionaries(sources);
+        let (embedded, _) = check_dictionaries(embedded_sources());
+        for (name, dictionary) in embedded {
+            loaded.entry(name).or_insert(dictionary);
+        }
+        *self
+            .dictionaries
+            .write()
+            .map_err(|e| ServiceError::Unknown(e.to_string()))? = loaded;
+        Ok(reports)
+    }
+
+    pub fn dictionaries(&self) -> Vec<DictionarySummary> {
+        let mut summaries: Vec<DictionarySummary> = self
+            .dictionaries
+            .read()
+            .expect("dictionary lock poisoned")
+            .iter()
+            .map(|(name, words)| DictionarySummary {
+                name: name.clone(),
+                size: words.len(),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+
+    fn dictionary(&self, name: &str) -> ServiceResult<Dictionary> {
+        self.dictionaries
+            .read()
+            .map_err(|e| ServiceError::Unknown(e.to_string()))?
+            .get(name)
+            .cloned()
+            .ok_or_else(|| {
+                ServiceError::Unprocessable(
+                    ErrorBody::new(
+                        "UNKNOWN_DICTIONARY",
+                        format!("no dictionary named {}", name),
+                    )
+                    .with_details(json!({ "dictionary": name })),
+                )
+            })
+    }
+
+    /// Draws the words for a board from a mix of dictionaries, each contributing in proportion to
+    /// its weight. An empty mix draws from the default dictionary.
+    ///
+    /// Words in `used` are left out, like cards already dealt from a deck, and the words drawn are
+    /// added to it. Once a dictionary runs out, its words are shuffled back in for whatever its
+    /// share still needs. The same dictionaries, used words and seed give the same words.
+    ///
+    /// Only dictionaries with difficulty tags can be drawn from at a difficulty other than mixed.
+    pub async fn new_word_set(
+        &self,
+        mix: &BTreeMap<String, u32>,
+        difficulty: BoardDifficulty,
+        used: BTreeSet<String>,
+        seed: u64,
+    ) -> ServiceResult<([String; 25], BTreeSet<String>)> {
+        let default = BTreeMap::from([(DEFAULT_DICTIONARY.to_string(), 1)]);
+        let mix = if mix.is_empty() { &default } else { mix };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut words: Vec<String> = Vec::new();
+        let mut used = used;
+        for (name, quota) in quotas(mix)? {
+            let dictionary = self.dictionary(name)?;
+            if difficulty != BoardDifficulty::Mixed
+                && dictionary
+                    .values()
+                    .all(|metadata| metadata.difficulty.is_none())
+            {
+                return Err(ServiceError::Unprocessable(
+                    ErrorBody::new(
+                        "UNTAGGED_DICTIONARY",
+                        format!(
+                            "dictionary {} has no word difficulties, so it can only be played at \
+                             mixed difficulty",
+                            name
+                        ),
+                    )
+                    .with_details(json!({ "dictionary": name })),
+                ));
+            }
+            let (drawn, now_used) = self
+                .draw(dictionary, difficulty, quota, &words, used, rng.gen())
+                .await?;
+            used = now_used.into_iter().chain(drawn.iter().cloned()).collect();
+            words.extend(drawn);
+        }
+        if mix.len() > 1 {
+            words.shuffle(&mut rng);
+        }
+
+        Ok((words.try_into().unwrap(), used))
+    }
+
+    /// Draws the words for a board from a custom pool, leaving out `used` words like
+    /// `new_word_set`.
+    pub async fn new_word_set_from(
+        &self,
+        pool: HashSet<String>,
+        used: BTreeSet<String>,
+        seed: u64,
+    ) -> ServiceResult<([String; 25], BTreeSet<String>)> {
+        let pool = pool
+            .into_iter()
+            .map(|word| (word, WordMetadata::default()))
+            .collect();
+        let (words, used) = self
+            .draw(pool, BoardDifficulty::Mixed, BOARD_SIZE, &[], used, seed)
+            .await?;
+        let used = used.into_iter().chain(words.iter().cloned()).collect();
+        Ok((words.try_into().unwrap(), used))
+    }
+
+    /// Draws `count` words of `dictionary` that are neither `taken` nor `used`. If fewer than that
+    /// are left, it draws all of them and makes up the rest from the used ones, forgetting that
+    /// those were used.
+    async fn draw(
+        &self,
+        dictionary: Dictionary,
+        difficulty: BoardDifficulty,
+        count: usize,
+        taken: &[String],
+        used: BTreeSet<String>,
+        seed: u64,
+    ) -> ServiceResult<(Vec<String>, BTreeSet<String>)> {
+        let (fresh, spent): (Dictionary, Dictionary) = dictionary
+            .into_iter()
+            .filter(|(word, _)| !taken.contains(word))
+            .partition(|(word, _)| !used.contains(word));
+        if fresh.len() >= count {
+            let words = self
+                .generator
+                .random_set(fresh, difficulty, count, seed)
+                .await?;
+            return Ok((words, used));
+        }
+
+        let used = used
+            .into_iter()
+            .filter(|word| !spent.contains_key(word))
+            .collect();
+        let short = count - fresh.len();
+        let fresh_count = fresh.len();
+        let mut words = [
+            self.generator
+                .random_set(fresh, difficulty, fresh_count, seed)
+                .await?,
+            self.generator
+                .random_set(spent, difficulty, short, seed)
+                .await?,
+        ]
+        .concat();
+        words.shuffle(&mut StdRng::seed_from_u64(seed));
+        Ok((words, used))
+    }
+
+    /// Draws a word to replace `word` on a board, from whichever dictionary of the mix it came
+    /// from. Words `taken` by the board are left out, and so are `used` ones while enough others
+    /// are left.
+    pub async fn new_word_like(
+        &self,
+        mix: &BTreeMap<String, u32>,
+        word: &str,
+        taken: &[String],
+        used: &BTreeSet<String>,
+    ) -> ServiceResult<String> {
+        let names: Vec<&str> = match mix.keys().map(|name| name.as_str()).collect::<Vec<_>>() {
+            names if names.is_empty() => vec![DEFAULT_DICTIONARY],
+            names => names,
+        };
+        let mut dictionaries = names
+            .into_iter()
+            .map(|name| self.dictionary(name))
+            .collect::<ServiceResult<Vec<Dictionary>>>()?;
+        let dictionary = dictionaries
+            .iter()
+            .position(|dictionary| dictionary.contains_key(word))
+            .map(|i| dictionaries.swap_remove(i))
+            .ok_or_else(|| {
+                ServiceError::Unprocessable(
+                    ErrorBody::new(
+                        "NOT_FROM_A_DICTIONARY",
+                        format!("{} is not from one of the game's dictionaries", word),
+                    )
+                    .with_details(json!({ "word": word })),
+                )
+            })?;
+
+        let (fresh, spent): (HashSet<String>, HashSet<String>) = dictionary
+            .into_keys()
+            .filter(|w| !taken.contains(w))
+            .partition(|w| !used.contains(w));
+        if !fresh.is_empty() {
+            self.generator.random_word(fresh).await
+        } else {
+            self.generator
+                .random_word(fresh.into_iter().chain(spent).collect())
+                .await
+        }
+    }
+
+    pub async fn new_word_pair(&self) -> ServiceResult<(String, String)> {
+        debug!("call: dictionary.Service.new_word_pair)");
+        self.generator
+            .random_pair(self.dictionary(DEFAULT_DICTIONARY)?.into_keys().collect())
+            .await
+    }
+}
+
+/// Splits the words of a board between dictionaries in proportion to their weights, handing the
+/// words left over by rounding down to the largest remainders.
+fn quotas(mix: &BTreeMap<String, u32>) -> ServiceResult<Vec<(&String, usize)>> {
+    let total: u64 = mix.values().map(|&w| w as u64).sum();
+    if total == 0 {
+        return Err(ServiceError::Unprocessable(ErrorBody::new(
+            "INVALID_DICTIONARY_MIX",
+            "dictionary weights must not all be zero".to_string(),
+        )));
+    }
+
+    let shares: Vec<(&String, usize, u64)> = mix
+        .iter()
+        .map(|(name, &weight)| {
+            let share = BOARD_SIZE as u64 * weight as u64;
+            (name, (share / total) as usize, share % total)
+        })
+        .collect();
+    let left_over = BOARD_SIZE - shares.iter().map(|(_, quota, _)| quota).sum::<usize>();
+
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|&a, &b| shares[b].2.cmp(&shares[a].2));
+    let extra: HashSet<usize> = by_remainder.into_iter().take(left_over).collect();
+
+    Ok(shares
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, quota, _))| (name, quota + extra.contains(&i) as usize))
+        .filter(|(_, quota)| quota > &0)
+        .collect())
+}
+
+#[async_trait]
+pub trait WordGenerator: DynClone + Send + Sync {
+    /// Draws `count` different words. The same dictionary and seed give the same words.
+    async fn random_set(
+        &self,
+        dictionary: Dictionary,
+        difficulty: BoardDifficulty,
+        count: usize,
+        seed: u64,
+    ) -> ServiceResult<Vec<String>>;
+    async fn random_pair(&self, dictionary: HashSet<String>) -> ServiceResult<(String, String)>;
+    async fn random_word(&self, dictionary: HashSet<String>) -> ServiceResult<String>;
+}
+
+dyn_clone::clone_trait_object!(WordGenerator);
+
+/// Parses and checks each source, keeping the dictionaries without errors.
+fn check_dictionaries(sources: Vec<DictionarySource>) -> (Dictionaries, Vec<DictionaryReport>) {
+    let mut dictionaries = Dictionaries::new();
+    let mut reports = Vec::new();
+    for source in sources {
+        let (dictionary, report) = parse_dictionary(&source.name, &source.contents);
+        if report.accepted {
+            dictionaries.insert(source.name, dictionary);
+        }
+        reports.push(report);
+    }
+    reports.sort_by(|a, b| a.name.cmp(&b.name));
+    (dictionaries, reports)
+}
+
+pub const MINIMUM_DICTIONARY_SIZE: usize = 25;
+
+pub const DEFAULT_DICTIONARY: &str = "default";
+
+/// The dictionaries bundled with the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictionaryType {
+    Default,
+    Animals,
+    Food,
+    Spanish,
+}
+
+impl DictionaryType {
+    pub const ALL: [DictionaryType; 4] = [
+        DictionaryType::Default,
+        DictionaryType::Animals,
+        DictionaryType::Food,
+        DictionaryType::Spanish,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DictionaryType::Default => DEFAULT_DICTIONARY,
+            DictionaryType::Animals => "animals",
+            DictionaryType::Food => "food",
+            DictionaryType::Spanish => "spanish",
+        }
+    }
+
+    pub fn source(&self) -> DictionarySource {
+        DictionarySource {
+            name: self.name().to_string(),
+            contents: self.contents().to_vec(),
+        }
+    }
+
+    fn contents(&self) -> &'static [u8] {
+        match self {
+            DictionaryType::Default => include_bytes!("default.txt"),
+            DictionaryType::Animals => include_bytes!("animals.txt"),
+            DictionaryType::Food => include_bytes!("food.txt"),
+            DictionaryType::Spanish => include_bytes!("spanish.txt"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WordGeneratorRand;
+
+#[async_trait]
+impl WordGenerator for WordGeneratorRand {
+    async fn random_set(
+        &self,
+        dictionary: Dictionary,
+        difficulty: BoardDifficulty,
+        count: usize,
+        seed: u64,
+    ) -> ServiceResult<Vec<String>> {
+        if dictionary.len() < count {
+            return Err(ServiceError::Unknown(format!(
+                "dictionary must have at least {} words",
+                count
+            )));
+        }
+
+        // Sorted, since the order of a HashMap differs from run to run.
+        let mut as_vector: Vec<(String, WordMetadata)> = dictionary.into_iter().collect();
+        as_vector.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let random_subset = as_vector
+            .choose_multiple_weighted(&mut StdRng::seed_from_u64(seed), count, |(_, metadata)| {
+                word_weight(difficulty, metadata.difficulty)
+            })
+            .map_err(|e| ServiceError::Unknown(e.to_string()))?
+            .map(|(word, _)| word.clone())
+            .collect();
+
+        Ok(random_subset)
+    }
+
+    async fn random_pair(&self, dictionary: HashSet<String>) -> ServiceResult<(String, String)> {
+        if dictionary.len() < (MINIMUM_DICTIONARY_SIZE + 1) {
+            return Err("dictionary must have at least 26 words".into());
+        }
+
+        let as_vector: Vec<String> = dictionary.into_iter().collect();
+
+        let random_subset: Vec<String> = as_vector
+            .choose_multiple(&mut thread_rng(), 2)
+            .cloned()
+            .collect();
+
+        let first = random_subset.first().unwrap().clone();
+        let second = random_subset.get(1).unwrap().clone();
+
+        Ok((first, second))
+    }
+
+    async fn random_word(&self, dictionary: HashSet<String>) -> ServiceResult<String> {
+        let as_vector: Vec<String> = dictionary.into_iter().collect();
+
+        as_vector
+            .choose(&mut thread_rng())
+            .cloned()
+            .ok_or_else(|| "dictionary must not be empty".into())
+    }
+}
+
+/// How likely a word is to be drawn for a board of the given difficulty. Words without a
+/// difficulty count as medium.
+fn word_weight(board: BoardDifficulty, word: Option<Difficulty>) -> f64 {
+    match (board, word.unwrap_or(Difficulty::Medium)) {
+        (BoardDifficulty::Mixed, _) => 1.0,
+        (BoardDifficulty::Easy, Difficulty::Easy) | (BoardDifficulty::Hard, Difficulty::Hard) => {
+            4.0
+        }
+        (_, Difficulty::Medium) => 2.0,
+        (BoardDifficulty::Easy, Difficulty::Hard) | (BoardDifficulty::Hard, Difficulty::Easy) => {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use rand::random;
+
+    use crate::{
+        dictionary::{
+            quotas, Dictionary, DictionaryService, Difficulty, WordGenerator, WordGeneratorRand,
+            WordMetadata,
+        },
+        game::model::BoardDifficulty,
+        ServiceError,
+    };
+
+    #[test]
+    fn quotas_follow_weights() {
+        let mix = BTreeMap::from([
+            ("a".to_string(), 2),
+            ("b".to_string(), 1),
+            ("c".to_string(), 0),
+        ]);
+        let quotas: Vec<(String, usize)> = quotas(&mix)
+            .unwrap()
+            .into_iter()
+            .map(|(name, quota)| (name.clone(), quota))
+            .collect();
+        assert_eq!(quotas, vec![("a".to_string(), 17), ("b".to_string(), 8)]);
+
+        assert!(super::quotas(&BTreeMap::from([("a".to_string(), 0)])).is_err());
+    }
+
+    #[tokio::test]
+    async fn difficulty_needs_tagged_dictionaries() {
+        let service = DictionaryService::new(Box::new(WordGeneratorRand)).unwrap();
+        let mix = |name: &str| BTreeMap::from([(name.to_string(), 1)]);
+
+        assert!(service
+            .new_word_set(&mix("default"), BoardDifficulty::Easy, BTreeSet::new(), 1)
+            .await
+            .is_ok());
+        assert!(service
+            .new_word_set(&mix("animals"), BoardDifficulty::Mixed, BTreeSet::new(), 1)
+            .await
+            .is_ok());
+        let error = service
+            .new_word_set(&mix("animals"), BoardDifficulty::Hard, BTreeSet::new(), 1)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, ServiceError::Unprocessable(_)));
+        assert_eq!(error.body().code, "UNTAGGED_DICTIONARY");
+    }
+
+    #[tokio::test]
+    async fn new_word_set_from_mix() {
+        let service = DictionaryService::new(Box::new(WordGeneratorRand)).unwrap();
+        let animals = service.dictionary("animals").unwrap();
+        let food = service.dictionary("food").unwrap();
+
+        let words = service
+            .new_word_set(
+                &BTreeMap::from([("animals".to_string(), 1), ("food".to_string(), 4)]),
+                BoardDifficulty::Mixed,
+                BTreeSet::new(),
+                1,
+            )
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(words.iter().filter(|&w| animals.contains_key(w)).count(), 5);
+        assert_eq!(words.iter().filter(|&w| food.contains_key(w)).count(), 20);
+
+        let unknown = service
+            .new_word_set(
+                &BTreeMap::from([("klingon".to_string(), 1)]),
+                BoardDifficulty::Mixed,
+                BTreeSet::new(),
+                1,
+            )
+            .await;
+        assert_eq!(unknown.err().unwrap().body().code, "UNKNOWN_DICTIONARY");
+    }
+
+    #[tokio::test]
+    async fn new_word_set_skips_used_words() {
+        let service = DictionaryService::new(Box::new(WordGeneratorRand)).unwrap();
+        let animals = BTreeMap::from([("animals".to_string(), 1)]);
+        let size = service.dictionary("animals").unwrap().len();
+        let deal = |used| service.new_word_set(&animals, BoardDifficulty::Mixed, used, random());
+
+        let (first, used) = deal(BTreeSet::new()).await.unwrap();
+        let (second, used) = deal(used).await.unwrap();
+        assert!(second.iter().all(|w| !first.contains(w)));
+        assert_eq!(used.len(), 50);
+
+        let mut used = used;
+        for _ in 2..size / 25 {
+            used = deal(used).await.unwrap().1;
+        }
+        let (last, used) = deal(used).await.unwrap();
+        assert_eq!(used, last.iter().cloned().collect());
+    }
+
+    #[tokio::test]
+    async fn draw_only_reuses_words_it_runs_short_of() {
+        let service = DictionaryService::new(Box::new(WordGeneratorRand)).unwrap();
+        let dictionary: Dictionary = (0..40)
+            .map(|i| (format!("word{}", i), WordMetadata::default()))
+            .collect();
+        let used: BTreeSet<String> = (26..40).map(|i| format!("word{}", i)).collect();
+
+        let (words, now_used) = service
+            .draw(
+                dictionary.clone(),
+                BoardDifficulty::Mixed,
+                25,
+                &[],
+                used.clone(),
+                random(),
+            )
+            .await
+            .unwrap();
+        assert!(words.iter().all(|w| !used.contains(w)));
+        assert_eq!(now_used, used);
+
+        let (words, now_used) = service
+            .draw(
+                dictionary,
+                BoardDifficulty::Mixed,
+                30,
+                &[],
+                used.clone(),
+                random(),
+            )
+            .await
+            .unwrap();
+        let reused = words.iter().filter(|&w| used.contains(w)).count();
+        assert_eq!(words.len(), 30);
+        assert_eq!(reused, 4);
+        assert!(now_used.is_empty());
+    }
+
+    #[tokio::test]
+    async fn random_set_follows_difficulty() {
+        let dictionary: Dictionary = (0..100)
+            .map(|i| {
+                let difficulty = if i < 50 {
+                    Difficulty::Easy
+                } else {
+                    Difficulty::Hard
+                };
+                let metadata = WordMetadata {
+                    difficulty: Some(difficulty),
+                    ..WordMetadata::default()
+                };
+                (format!("word{}", i), metadata)
+            })
+            .collect();
+        let count_easy = |words: Vec<String>| {
+            words
+                .iter()
+                .filter(|w| dictionary[*w].difficulty == Some(Difficulty::Easy))
+                .count()
+        };
+
+        let mut easy = 0;
+        let mut hard = 0;
+        for _ in 0..20 {
+            easy += count_easy(
+                WordGeneratorRand
+                    .random_set(dictionary.clone(), BoardDifficulty::Easy, 25, random())
+                    .await
+                    .unwrap(),
+            );
+            hard += count_easy(
+                WordGeneratorRand
+                    .random_set(dictionary.clone(), BoardDifficulty::Hard, 25, random())
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert!(easy > 300, "easy boards drew {} easy words of 500", easy);
+        assert!(hard < 200, "hard boards drew {} easy words of 500", hard);
+    }
+}
@@ -1,34 +1,48 @@
 use std::convert::TryInto;
 
 use async_trait::async_trait;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
 use crate::{
     game::{
         board_service::BoardGenerator,
+        key_card::{BoardGeneratorKeyCard, KeyCard},
         model::{Board, Card, CardColor, CardState, Team},
     },
-    ServiceResult,
+    ServiceResult, StdResult,
 };
-use rand::{seq::SliceRandom, thread_rng};
+
+/// Picks the `BoardGenerator` from `CODENAMES_BOARDS`: `random` (the default) shuffles the colors,
+/// `key-card` follows the bundled key cards.
+pub fn board_generator_from_env() -> StdResult<Box<dyn BoardGenerator>> {
+    match std::env::var("CODENAMES_BOARDS").ok().as_deref() {
+        None | Some("") | Some("random") => Ok(Box::new(BoardGeneratorRand)),
+        Some("key-card") => Ok(Box::new(BoardGeneratorKeyCard::new()?)),
+        Some(other) => Err(format!("unknown CODENAMES_BOARDS: {}", other).into()),
+    }
+}
 
 #[derive(Clone)]
 pub struct BoardGeneratorRand;
 
 impl BoardGeneratorRand {
-    fn random_team(&self) -> Team {
-        vec![Team::Blue, Team::Red]
-            .choose(&mut thread_rng())
-            .unwrap()
-            .clone()
+    fn random_team(&self, rng: &mut StdRng) -> Team {
+        *[Team::Blue, Team::Red].choose(rng).unwrap()
     }
 }
 
 #[async_trait]
 impl BoardGenerator for BoardGeneratorRand {
-    async fn random_board(&self, words: [String; 25]) -> ServiceResult<(Board, Team)> {
-        let first_team = self.random_team();
+    async fn random_board(
+        &self,
+        words: [String; 25],
+        seed: u64,
+    ) -> ServiceResult<(Board, Team, Option<KeyCard>)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let first_team = self.random_team(&mut rng);
 
         let mut indices: Vec<usize> = (0..25).collect();
-        indices.shuffle(&mut thread_rng());
+        indices.shuffle(&mut rng);
 
         let mut initial_board: Vec<CardState> = words
             .iter()
@@ -66,21 +80,21 @@ impl BoardGenerator for BoardGeneratorRand {
             })
             .collect();
 
-        Ok((board.try_into().unwrap(), first_team))
+        Ok((board.try_into().unwrap(), first_team, None))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
 
     use crate::{
-        dictionary::DictionaryService,
+        dictionary::{DictionaryService, WordGeneratorRand},
         game::{
+            board::BoardGeneratorRand,
             board_service::{card_color_count, BoardService},
-            model::{CardColor, Team},
+            model::{BoardDifficulty, CardColor, Team},
         },
-        dictionary::WordGeneratorRand,
-        game::board::BoardGeneratorRand,
     };
 
     #[tokio::test]
@@ -89,8 +103,15 @@ mod tests {
             DictionaryService::new(Box::new(WordGeneratorRand {})).unwrap();
         let test_service = BoardService::new(Box::new(BoardGeneratorRand {}));
 
-        let (board, first_team) = test_service
-            .new_board(test_dictionary_service.new_word_set().await.unwrap())
+        let (board, first_team, _) = test_service
+            .new_board(
+                test_dictionary_service
+                    .new_word_set(&BTreeMap::new(), BoardDifficulty::Mixed, BTreeSet::new(), 1)
+                    .await
+                    .unwrap()
+                    .0,
+                1,
+            )
             .await
             .unwrap();
 
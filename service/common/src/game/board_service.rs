@@ -2,7 +2,10 @@ use async_trait::async_trait;
 use dyn_clone::DynClone;
 
 use crate::{
-    game::model::{Board, Card, CardColor, Team},
+    game::{
+        key_card::KeyCard,
+        model::{Board, Card, CardColor, Team},
+    },
     ServiceResult,
 };
 
@@ -16,14 +19,24 @@ impl BoardService {
         BoardService { generator }
     }
 
-    pub async fn new_board(&self, words: [String; 25]) -> ServiceResult<(Board, Team)> {
-        self.generator.random_board(words).await
+    /// Colors the cards and picks the first team, along with the key card the colors follow if
+    /// the generator uses them. The same words and seed give the same board.
+    pub async fn new_board(
+        &self,
+        words: [String; 25],
+        seed: u64,
+    ) -> ServiceResult<(Board, Team, Option<KeyCard>)> {
+        self.generator.random_board(words, seed).await
     }
 }
 
 #[async_trait]
 pub trait BoardGenerator: DynClone + Send + Sync {
-    async fn random_board(&self, words: [String; 25]) -> ServiceResult<(Board, Team)>;
+    async fn random_board(
+        &self,
+        words: [String; 25],
+        seed: u64,
+    ) -> ServiceResult<(Board, Team, Option<KeyCard>)>;
 }
 
 dyn_clone::clone_trait_object!(BoardGenerator);
@@ -41,7 +54,7 @@ pub fn max_card_color(card_color: &CardColor, first_team: &Team) -> usize {
     }
 }
 
-pub fn card_color_count(partial_board: &Vec<Card>, color: &CardColor) -> usize {
+pub fn card_color_count(partial_board: &[Card], color: &CardColor) -> usize {
     partial_board
         .iter()
         .filter(|card| &card.color == color)
@@ -0,0 +1,154 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::game::model::{CardColor, GameData, Team, Turn};
+
+/// The seed of the board of the day. Keyed with a server secret, so the board cannot be worked out
+/// from the date alone.
+pub fn daily_seed(key: &str, date: NaiveDate) -> u64 {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(date.to_string().as_bytes());
+    let bytes = mac.finalize().into_bytes();
+    u64::from_be_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// How one team did on the board of the day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub game_name: String,
+    pub team: Team,
+    pub won: bool,
+    /// Clues the team's spymaster gave.
+    pub turns: usize,
+    /// Guesses that revealed a card that was not the team's own.
+    pub mistakes: usize,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl DailyResult {
+    /// The results of both teams of a finished game, or none if it is not over yet.
+    pub fn from_game(game: &GameData, finished_at: DateTime<Utc>) -> Vec<DailyResult> {
+        let winner = match game.winner() {
+            Some(winner) => winner,
+            None => return Vec::new(),
+        };
+        [Team::Blue, Team::Red]
+            .iter()
+            .map(|&team| {
+                let turns = game.info.turns().iter().filter_map(|turn| match turn {
+                    Turn::Started(data) if data.spymaster.team == team => Some(data),
+                    _ => None,
+                });
+                DailyResult {
+                    game_name: game.info.name().to_string(),
+                    team,
+                    won: team == winner,
+                    turns: turns.clone().count(),
+                    mistakes: turns
+                        .flat_map(|data| data.guesses.iter())
+                        .filter(|guess| game.board[guess.index].color != CardColor::Team(team))
+                        .count(),
+                    finished_at,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Orders the results of a day: winners first, then by fewest turns, fewest mistakes and who
+/// finished first.
+pub fn leaderboard(results: Vec<DailyResult>) -> Vec<DailyResult> {
+    let mut results = results;
+    results.sort_by(|a, b| {
+        b.won
+            .cmp(&a.won)
+            .then(a.turns.cmp(&b.turns))
+            .then(a.mistakes.cmp(&b.mistakes))
+            .then(a.finished_at.cmp(&b.finished_at))
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use chrono::{NaiveDate, Utc};
+
+    use crate::game::{
+        daily::{daily_seed, leaderboard, DailyResult},
+        model::{Card, CardColor, GameData, GameSettings, Player, Team},
+    };
+
+    fn finished_game() -> GameData {
+        let cards: Vec<Card> = (0..25)
+            .map(|i| Card {
+                color: match i {
+                    blue if blue < 9 => CardColor::Team(Team::Blue),
+                    red if red < 17 => CardColor::Team(Team::Red),
+                    24 => CardColor::Death,
+                    _ => CardColor::Neutral,
+                },
+                word: i.to_string(),
+            })
+            .collect();
+        let game = GameData::new(
+            "daily".to_string(),
+            cards.try_into().unwrap(),
+            Team::Blue,
+            GameSettings::default(),
+        );
+        let players = [
+            ("blue-spy", Team::Blue, true),
+            ("blue-op", Team::Blue, false),
+            ("red-spy", Team::Red, true),
+            ("red-op", Team::Red, false),
+        ];
+        let game = players.iter().fold(game, |game, &(name, team, spymaster)| {
+            game.join(Player {
+                team,
+                name: name.to_string(),
+                spymaster_secret: if spymaster { Some("".into()) } else { None },
+            })
+            .unwrap()
+        });
+
+        game.start_turn("blue-spy".to_string(), ("clue".to_string(), 2))
+            .unwrap()
+            .guess(("blue-op", 0))
+            .unwrap()
+            .guess(("blue-op", 20))
+            .unwrap()
+            .end_turn()
+            .start_turn("red-spy".to_string(), ("clue".to_string(), 1))
+            .unwrap()
+            .guess(("red-op", 24))
+            .unwrap()
+    }
+
+    #[test]
+    fn results_of_both_teams() {
+        let results = leaderboard(DailyResult::from_game(&finished_game(), Utc::now()));
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.team, r.won, r.turns, r.mistakes))
+                .collect::<Vec<_>>(),
+            vec![(Team::Blue, true, 1, 1), (Team::Red, false, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn seed_depends_on_key_and_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let next = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        assert_eq!(daily_seed("key", date), daily_seed("key", date));
+        assert_ne!(daily_seed("key", date), daily_seed("key", next));
+        assert_ne!(daily_seed("key", date), daily_seed("other", date));
+    }
+}
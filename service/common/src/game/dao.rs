@@ -1,10 +1,15 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 use async_trait::async_trait;
 use aws_sdk_dynamodb::{types::AttributeValue, Client};
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDate, Utc};
 use dyn_clone::DynClone;
 
 use crate::{
-    game::model::GameData,
+    game::{daily::DailyResult, model::GameData},
     DaoError,
     DaoError::NotFound,
     DaoResult, Lowercase, StdResult,
@@ -14,7 +19,21 @@ use crate::{
 pub trait GameDao: DynClone + Send + Sync {
     async fn get(&mut self, key: Lowercase) -> DaoResult<GameData>;
     async fn keys(&mut self) -> DaoResult<Vec<Lowercase>>;
-    async fn set(&mut self, key: Lowercase, game: GameData) -> DaoResult<()>;
+    /// Saves a game. With `expected_version`, the stored game must still be at that version, or
+    /// the save fails with `DaoError::Conflict`.
+    async fn set(
+        &mut self,
+        key: Lowercase,
+        game: GameData,
+        expected_version: Option<u64>,
+    ) -> DaoResult<()>;
+    async fn get_word_pool(&mut self, name: Lowercase) -> DaoResult<Vec<String>>;
+    /// Saves a new word pool, failing with `DaoError::Conflict` if the name is taken.
+    async fn set_word_pool(&mut self, name: Lowercase, words: Vec<String>) -> DaoResult<()>;
+    /// The dictionaries an admin keeps in the store, apart from the word pools players save.
+    async fn dictionaries(&mut self) -> DaoResult<HashMap<Lowercase, Vec<String>>>;
+    async fn add_daily_result(&mut self, date: NaiveDate, result: DailyResult) -> DaoResult<()>;
+    async fn daily_results(&mut self, date: NaiveDate) -> DaoResult<Vec<DailyResult>>;
 }
 
 dyn_clone::clone_trait_object!(GameDao);
@@ -23,6 +42,18 @@ const DYNAMO_TABLE_NAME: &str = "codenames";
 const DYNAMO_KEY_ATTRIBUTE: &str = "key";
 const DYNAMO_TTL_ATTRIBUTE: &str = "ttl";
 const DYNAMO_GAME_ATTRIBUTE: &str = "game";
+/// The game's version, kept beside the serialized game so writes can be made conditional on it.
+const DYNAMO_VERSION_ATTRIBUTE: &str = "version";
+const DYNAMO_WORDS_ATTRIBUTE: &str = "words";
+const DYNAMO_RESULTS_ATTRIBUTE: &str = "results";
+/// Word pools and daily results share the table with games. Game names cannot contain `#`, so the
+/// keys never clash.
+const DYNAMO_RESERVED_PREFIX: &str = "#";
+const DYNAMO_WORD_POOL_PREFIX: &str = "#word-pool/";
+const DYNAMO_DICTIONARY_PREFIX: &str = "#dictionary/";
+const DYNAMO_DAILY_PREFIX: &str = "#daily/";
+/// How long the results of a daily challenge are kept.
+const DAILY_RESULTS_DAYS: i64 = 30;
 
 #[derive(Clone)]
 pub struct DynamoDao {
@@ -39,6 +70,14 @@ impl DynamoDao {
     fn get_ttl() -> i64 {
         Utc::now().timestamp() + Duration::days(1).num_seconds()
     }
+
+    fn word_pool_key(name: &Lowercase) -> String {
+        format!("{}{}", DYNAMO_WORD_POOL_PREFIX, name.value())
+    }
+
+    fn daily_key(date: &NaiveDate) -> String {
+        format!("{}{}", DYNAMO_DAILY_PREFIX, date)
+    }
 }
 
 #[async_trait]
@@ -83,20 +122,29 @@ impl GameDao for DynamoDao {
             .iter()
             .map(|i| {
                 i.get(DYNAMO_KEY_ATTRIBUTE)
-                    .expect(format!("No {} field in response", DYNAMO_KEY_ATTRIBUTE).as_str())
+                    .unwrap_or_else(|| panic!("No {} field in response", DYNAMO_KEY_ATTRIBUTE))
             })
             .map(|a| {
                 a.as_s()
-                    .expect(format!("{} field was not a string", DYNAMO_KEY_ATTRIBUTE).as_str())
+                    .unwrap_or_else(|_| panic!("{} field was not a string", DYNAMO_KEY_ATTRIBUTE))
             })
+            .filter(|k| !k.starts_with(DYNAMO_RESERVED_PREFIX))
             .cloned()
             .collect();
 
         Ok(keys.iter().map(|k| Lowercase::new(k)).collect())
     }
 
-    async fn set(&mut self, key: Lowercase, game: GameData) -> DaoResult<()> {
-        self.client
+    /// Games saved before the version was stored beside them have no version to compare, so
+    /// their first conditional write goes through.
+    async fn set(
+        &mut self,
+        key: Lowercase,
+        game: GameData,
+        expected_version: Option<u64>,
+    ) -> DaoResult<()> {
+        let put = self
+            .client
             .put_item()
             .table_name(DYNAMO_TABLE_NAME)
             .item(
@@ -107,13 +155,354 @@ impl GameDao for DynamoDao {
                 DYNAMO_TTL_ATTRIBUTE,
                 AttributeValue::N(DynamoDao::get_ttl().to_string()),
             )
+            .item(
+                DYNAMO_VERSION_ATTRIBUTE,
+                AttributeValue::N(game.info.version().to_string()),
+            )
             .item(
                 DYNAMO_GAME_ATTRIBUTE,
-                AttributeValue::S(serde_json::to_string(&game).map_err(|e| DaoError::Unknown(e.to_string()))?),
+                AttributeValue::S(
+                    serde_json::to_string(&game).map_err(|e| DaoError::Unknown(e.to_string()))?,
+                ),
+            );
+        let put = match expected_version {
+            Some(version) => put
+                .condition_expression("attribute_not_exists(#v) OR #v = :version")
+                .expression_attribute_names("#v", DYNAMO_VERSION_ATTRIBUTE)
+                .expression_attribute_values(":version", AttributeValue::N(version.to_string())),
+            None => put,
+        };
+        put.send()
+            .await
+            .map(|_| ())
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_conditional_check_failed_exception() => {
+                    DaoError::Conflict(format!("game {} has changed", key.value()))
+                }
+                e => DaoError::Unknown(e.to_string()),
+            })
+    }
+
+    async fn get_word_pool(&mut self, name: Lowercase) -> DaoResult<Vec<String>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(DYNAMO_TABLE_NAME)
+            .key(
+                DYNAMO_KEY_ATTRIBUTE,
+                AttributeValue::S(DynamoDao::word_pool_key(&name)),
+            )
+            .send()
+            .await
+            .map_err(|e| DaoError::Unknown(e.to_string()))?;
+
+        let item = result.item.ok_or(NotFound(name.value().to_string()))?;
+        let words = item
+            .get(DYNAMO_WORDS_ATTRIBUTE)
+            .ok_or(DaoError::Unknown(
+                "could not find words attribute on dynamo result".into(),
+            ))?
+            .as_s()
+            .map_err(|_| DaoError::Unknown("could not get words as string".into()))?;
+
+        serde_json::from_str(words).map_err(|e| DaoError::Unknown(e.to_string()))
+    }
+
+    /// Dictionaries are kept like word pools, as a list of words, under keys of their own so that
+    /// only whoever manages the table can add them.
+    async fn dictionaries(&mut self) -> DaoResult<HashMap<Lowercase, Vec<String>>> {
+        let result = self
+            .client
+            .scan()
+            .table_name(DYNAMO_TABLE_NAME)
+            .filter_expression("begins_with(#k, :prefix)")
+            .expression_attribute_names("#k", DYNAMO_KEY_ATTRIBUTE)
+            .expression_attribute_values(
+                ":prefix",
+                AttributeValue::S(DYNAMO_DICTIONARY_PREFIX.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| DaoError::Unknown(e.to_string()))?;
+
+        result
+            .items()
+            .iter()
+            .map(|item| {
+                let key = item
+                    .get(DYNAMO_KEY_ATTRIBUTE)
+                    .and_then(|a| a.as_s().ok())
+                    .ok_or(DaoError::Unknown("dictionary without a key".into()))?;
+                let words = item
+                    .get(DYNAMO_WORDS_ATTRIBUTE)
+                    .and_then(|a| a.as_s().ok())
+                    .ok_or(DaoError::Unknown(format!(
+                        "dictionary {} without words",
+                        key
+                    )))?;
+                Ok((
+                    Lowercase::new(key.trim_start_matches(DYNAMO_DICTIONARY_PREFIX)),
+                    serde_json::from_str(words).map_err(|e| DaoError::Unknown(e.to_string()))?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Word pools are kept without a TTL, so they outlive the games they were created with. The
+    /// write is conditional, so a pool someone else saved first is never overwritten.
+    async fn set_word_pool(&mut self, name: Lowercase, words: Vec<String>) -> DaoResult<()> {
+        self.client
+            .put_item()
+            .table_name(DYNAMO_TABLE_NAME)
+            .condition_expression("attribute_not_exists(#k)")
+            .expression_attribute_names("#k", DYNAMO_KEY_ATTRIBUTE)
+            .item(
+                DYNAMO_KEY_ATTRIBUTE,
+                AttributeValue::S(DynamoDao::word_pool_key(&name)),
+            )
+            .item(
+                DYNAMO_WORDS_ATTRIBUTE,
+                AttributeValue::S(
+                    serde_json::to_string(&words).map_err(|e| DaoError::Unknown(e.to_string()))?,
+                ),
             )
             .send()
             .await
             .map(|_| ())
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_conditional_check_failed_exception() => {
+                    DaoError::Conflict(format!("word pool {} already exists", name.value()))
+                }
+                e => DaoError::Unknown(e.to_string()),
+            })
+    }
+
+    /// Appends to the day's results in one update, so teams finishing together do not overwrite
+    /// each other.
+    async fn add_daily_result(&mut self, date: NaiveDate, result: DailyResult) -> DaoResult<()> {
+        let result =
+            serde_json::to_string(&result).map_err(|e| DaoError::Unknown(e.to_string()))?;
+        let ttl = Utc::now().timestamp() + Duration::days(DAILY_RESULTS_DAYS).num_seconds();
+        self.client
+            .update_item()
+            .table_name(DYNAMO_TABLE_NAME)
+            .key(
+                DYNAMO_KEY_ATTRIBUTE,
+                AttributeValue::S(DynamoDao::daily_key(&date)),
+            )
+            .update_expression(
+                "SET #r = list_append(if_not_exists(#r, :empty), :result), #t = :ttl",
+            )
+            .expression_attribute_names("#r", DYNAMO_RESULTS_ATTRIBUTE)
+            .expression_attribute_names("#t", DYNAMO_TTL_ATTRIBUTE)
+            .expression_attribute_values(":empty", AttributeValue::L(Vec::new()))
+            .expression_attribute_values(
+                ":result",
+                AttributeValue::L(vec![AttributeValue::S(result)]),
+            )
+            .expression_attribute_values(":ttl", AttributeValue::N(ttl.to_string()))
+            .send()
+            .await
+            .map(|_| ())
             .map_err(|e| DaoError::Unknown(e.to_string()))
     }
+
+    async fn daily_results(&mut self, date: NaiveDate) -> DaoResult<Vec<DailyResult>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(DYNAMO_TABLE_NAME)
+            .key(
+                DYNAMO_KEY_ATTRIBUTE,
+                AttributeValue::S(DynamoDao::daily_key(&date)),
+            )
+            .send()
+            .await
+            .map_err(|e| DaoError::Unknown(e.to_string()))?;
+
+        let item = match result.item {
+            Some(item) => item,
+            None => return Ok(Vec::new()),
+        };
+        item.get(DYNAMO_RESULTS_ATTRIBUTE)
+            .and_then(|a| a.as_l().ok())
+            .ok_or(DaoError::Unknown(
+                "could not find results attribute on dynamo result".into(),
+            ))?
+            .iter()
+            .map(|a| {
+                let result = a
+                    .as_s()
+                    .map_err(|_| DaoError::Unknown("could not get result as string".into()))?;
+                serde_json::from_str(result).map_err(|e| DaoError::Unknown(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Keeps games in process memory, for running the service locally without AWS.
+#[derive(Clone, Default)]
+pub struct MemoryDao {
+    games: Arc<Mutex<HashMap<Lowercase, GameData>>>,
+    word_pools: Arc<Mutex<HashMap<Lowercase, Vec<String>>>>,
+    dictionaries: Arc<Mutex<HashMap<Lowercase, Vec<String>>>>,
+    daily_results: Arc<Mutex<HashMap<NaiveDate, Vec<DailyResult>>>>,
+}
+
+impl MemoryDao {
+    pub fn new() -> MemoryDao {
+        MemoryDao::default()
+    }
+
+    /// Adds a dictionary to the store, as an admin would.
+    pub fn with_dictionary(self, name: &str, words: Vec<String>) -> MemoryDao {
+        self.dictionaries
+            .lock()
+            .expect("dictionary lock poisoned")
+            .insert(Lowercase::new(name), words);
+        self
+    }
+}
+
+#[async_trait]
+impl GameDao for MemoryDao {
+    async fn get(&mut self, key: Lowercase) -> DaoResult<GameData> {
+        self.games
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .get(&key)
+            .cloned()
+            .ok_or(NotFound(key.value().to_string()))
+    }
+
+    async fn keys(&mut self) -> DaoResult<Vec<Lowercase>> {
+        Ok(self
+            .games
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    async fn set(
+        &mut self,
+        key: Lowercase,
+        game: GameData,
+        expected_version: Option<u64>,
+    ) -> DaoResult<()> {
+        let mut games = self
+            .games
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?;
+        if let Some(version) = expected_version {
+            if games.get(&key).map(|game| game.info.version()) != Some(version) {
+                return Err(DaoError::Conflict(format!(
+                    "game {} has changed",
+                    key.value()
+                )));
+            }
+        }
+        games.insert(key, game);
+        Ok(())
+    }
+
+    async fn get_word_pool(&mut self, name: Lowercase) -> DaoResult<Vec<String>> {
+        self.word_pools
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .get(&name)
+            .cloned()
+            .ok_or(NotFound(name.value().to_string()))
+    }
+
+    async fn set_word_pool(&mut self, name: Lowercase, words: Vec<String>) -> DaoResult<()> {
+        let mut word_pools = self
+            .word_pools
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?;
+        if word_pools.contains_key(&name) {
+            return Err(DaoError::Conflict(format!(
+                "word pool {} already exists",
+                name.value()
+            )));
+        }
+        word_pools.insert(name, words);
+        Ok(())
+    }
+
+    async fn dictionaries(&mut self) -> DaoResult<HashMap<Lowercase, Vec<String>>> {
+        Ok(self
+            .dictionaries
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .clone())
+    }
+
+    async fn add_daily_result(&mut self, date: NaiveDate, result: DailyResult) -> DaoResult<()> {
+        self.daily_results
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .entry(date)
+            .or_default()
+            .push(result);
+        Ok(())
+    }
+
+    async fn daily_results(&mut self, date: NaiveDate) -> DaoResult<Vec<DailyResult>> {
+        Ok(self
+            .daily_results
+            .lock()
+            .map_err(|e| DaoError::Unknown(e.to_string()))?
+            .get(&date)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use crate::{
+        game::{
+            dao::{GameDao, MemoryDao},
+            model::{Card, CardColor, GameData, GameSettings, Team},
+        },
+        DaoError, Lowercase,
+    };
+
+    #[tokio::test]
+    async fn conditional_set() {
+        let cards: Vec<Card> = (0..25)
+            .map(|i| Card {
+                color: CardColor::Neutral,
+                word: i.to_string(),
+            })
+            .collect();
+        let game = GameData::new(
+            "foo".to_string(),
+            cards.try_into().unwrap(),
+            Team::Blue,
+            GameSettings::default(),
+        );
+        let next = GameData {
+            info: game.info.clone().next_version(),
+            ..game.clone()
+        };
+        let key = Lowercase::new("foo");
+        let mut dao = MemoryDao::new();
+
+        assert!(matches!(
+            dao.set(key.clone(), game.clone(), Some(0)).await,
+            Err(DaoError::Conflict(_))
+        ));
+        dao.set(key.clone(), game, None).await.unwrap();
+        dao.set(key.clone(), next.clone(), Some(0)).await.unwrap();
+        assert!(matches!(
+            dao.set(key.clone(), next, Some(0)).await,
+            Err(DaoError::Conflict(_))
+        ));
+        assert_eq!(dao.get(key).await.unwrap().info.version(), 1);
+    }
 }
@@ -0,0 +1,197 @@
+use std::convert::TryInto;
+
+use async_trait::async_trait;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        board_service::{max_card_color, BoardGenerator},
+        model::{Board, Card, CardColor, Team, BOARD_SIZE},
+    },
+    ServiceError, ServiceResult, StdResult,
+};
+
+const GRID_SIZE: usize = 5;
+
+/// Which key card a board follows and how far it is turned, clockwise, so players with a physical
+/// deck can pick up the same card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyCard {
+    pub id: String,
+    pub rotation: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyLayout {
+    id: String,
+    first_team: Team,
+    colors: [CardColor; BOARD_SIZE],
+}
+
+impl KeyLayout {
+    /// The colors as they are with the card turned `quarter_turns` times clockwise.
+    fn rotated(&self, quarter_turns: usize) -> [CardColor; BOARD_SIZE] {
+        (0..quarter_turns).fold(self.colors, |colors, _| {
+            let mut turned = colors;
+            for row in 0..GRID_SIZE {
+                for column in 0..GRID_SIZE {
+                    turned[row * GRID_SIZE + column] =
+                        colors[(GRID_SIZE - 1 - column) * GRID_SIZE + row];
+                }
+            }
+            turned
+        })
+    }
+}
+
+/// Colors boards like the key cards of the physical game instead of shuffling them.
+#[derive(Clone)]
+pub struct BoardGeneratorKeyCard {
+    layouts: Vec<KeyLayout>,
+}
+
+impl BoardGeneratorKeyCard {
+    pub fn new() -> StdResult<BoardGeneratorKeyCard> {
+        let layouts = parse_key_cards(include_str!("key_cards.txt"))?;
+        Ok(BoardGeneratorKeyCard { layouts })
+    }
+}
+
+#[async_trait]
+impl BoardGenerator for BoardGeneratorKeyCard {
+    async fn random_board(
+        &self,
+        words: [String; 25],
+        seed: u64,
+    ) -> ServiceResult<(Board, Team, Option<KeyCard>)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let layout = self
+            .layouts
+            .choose(&mut rng)
+            .ok_or_else(|| ServiceError::Unknown("there are no key cards".into()))?;
+        let quarter_turns = rng.gen_range(0..4);
+
+        let board: Vec<Card> = words
+            .iter()
+            .zip(layout.rotated(quarter_turns).iter())
+            .map(|(word, &color)| Card {
+                word: word.clone(),
+                color,
+            })
+            .collect();
+        let key_card = KeyCard {
+            id: layout.id.clone(),
+            rotation: quarter_turns as u16 * 90,
+        };
+
+        Ok((board.try_into().unwrap(), layout.first_team, Some(key_card)))
+    }
+}
+
+/// Reads key cards, one per line: the id, the team that starts (`B` or `R`) and five rows of
+/// colors separated by `/`.
+fn parse_key_cards(contents: &str) -> StdResult<Vec<KeyLayout>> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_key_card)
+        .collect()
+}
+
+fn parse_key_card(line: &str) -> StdResult<KeyLayout> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (id, first_team, rows) = match fields.as_slice() {
+        [id, first_team, rows] => (id, first_team, rows),
+        _ => return Err(format!("key card should have three fields: {}", line).into()),
+    };
+    let team = |code: &str| match code {
+        "B" => Ok(Team::Blue),
+        "R" => Ok(Team::Red),
+        other => Err(format!("key card {} has unknown team {}", id, other)),
+    };
+    let first_team = team(first_team)?;
+    let colors = rows
+        .split('/')
+        .flat_map(|row| row.chars())
+        .map(|cell| match cell {
+            'B' => Ok(CardColor::Team(Team::Blue)),
+            'R' => Ok(CardColor::Team(Team::Red)),
+            'N' => Ok(CardColor::Neutral),
+            'A' => Ok(CardColor::Death),
+            other => Err(format!("key card {} has unknown color {}", id, other)),
+        })
+        .collect::<Result<Vec<CardColor>, String>>()?;
+
+    let counts_match = [
+        CardColor::Team(Team::Blue),
+        CardColor::Team(Team::Red),
+        CardColor::Neutral,
+        CardColor::Death,
+    ]
+    .iter()
+    .all(|color| {
+        colors.iter().filter(|&c| c == color).count() == max_card_color(color, &first_team)
+    });
+    if colors.len() != BOARD_SIZE || !counts_match {
+        return Err(format!("key card {} does not have the colors of a board", id).into());
+    }
+
+    Ok(KeyLayout {
+        id: id.to_string(),
+        first_team,
+        colors: colors.try_into().unwrap(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, convert::TryInto};
+
+    use crate::game::{
+        board_service::{card_color_count, BoardGenerator},
+        key_card::{parse_key_card, BoardGeneratorKeyCard},
+        model::CardColor,
+    };
+
+    #[test]
+    fn bundled_key_cards_are_valid() {
+        let generator = BoardGeneratorKeyCard::new().unwrap();
+        let ids: HashSet<&str> = generator.layouts.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids.len(), generator.layouts.len());
+
+        assert!(parse_key_card("X B BBBBB/BBBBR/RRRRR/RRRNN/NNNNA").is_err());
+    }
+
+    #[test]
+    fn rotation() {
+        let layout = parse_key_card("X R ABBBB/BBBBR/RRRRR/RRRNN/NNNNN").unwrap();
+        assert_eq!(layout.rotated(0), layout.colors);
+        assert_eq!(layout.rotated(1)[4], CardColor::Death);
+        assert_eq!(layout.rotated(2)[24], CardColor::Death);
+        assert_eq!(layout.rotated(3)[20], CardColor::Death);
+        assert_eq!(layout.rotated(4), layout.colors);
+    }
+
+    #[tokio::test]
+    async fn random_board() {
+        let generator = BoardGeneratorKeyCard::new().unwrap();
+        let words: [String; 25] = (0..25)
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>()
+            .try_into()
+            .unwrap();
+
+        let (board, first_team, key_card) = generator.random_board(words.clone(), 7).await.unwrap();
+        let key_card = key_card.unwrap();
+        assert!(generator.layouts.iter().any(|l| l.id == key_card.id));
+        assert!([0, 90, 180, 270].contains(&key_card.rotation));
+        assert_eq!(card_color_count(&board, &CardColor::Team(first_team)), 9);
+        assert_eq!(card_color_count(&board, &CardColor::Death), 1);
+        assert_eq!(board[3].word, "3");
+
+        let again = generator.random_board(words, 7).await.unwrap();
+        assert_eq!(again, (board, first_team, Some(key_card)));
+    }
+}
@@ -1,5 +1,8 @@
 pub mod board;
 pub mod board_service;
+pub mod daily;
 pub mod dao;
+pub mod key_card;
 pub mod model;
+pub mod observer;
 pub mod service;
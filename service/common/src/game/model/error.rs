@@ -1,18 +1,36 @@
 use std::{error::Error, fmt, fmt::Formatter};
 
-use crate::{ServiceError, UniqueError};
+use serde_json::{json, Value};
+
+use crate::{game::model::Team, ErrorBody, ServiceError, UniqueError};
 
 #[derive(Debug, PartialEq)]
 pub enum GameError {
     UniquePlayerName(UniqueError),
     UniqueGuess(UniqueError),
+    UniqueSpymaster(UniqueError),
     PlayerNotFound(String),
     WrongTeam(String),
     NotASpymaster(String),
     NotAnOperative(String),
+    NotATeammate(String),
     InvalidGuess(String),
+    InvalidName(String),
+    InvalidWordList(String),
+    InvalidSettings(String),
     TurnStarted,
     TurnPending,
+    TeamIncomplete(Team),
+    GameNotStarted,
+    GameStarted,
+    NotTheHost(String),
+    PlayersNotReady(Vec<String>),
+    NothingToUndo,
+    UndoExpired,
+    NotTheActor(String),
+    ResultRecorded,
+    RerollClosed,
+    RerollLimit(u32),
 }
 
 impl GameError {
@@ -33,6 +51,76 @@ impl GameError {
             guess.to_string(),
         ))
     }
+    pub fn unique_spymaster(team: Team, spymaster_name: String) -> GameError {
+        GameError::UniqueSpymaster(UniqueError::new(
+            format!("{} team", team),
+            "spymaster".to_string(),
+            spymaster_name,
+        ))
+    }
+
+    /// A stable, machine-readable identifier for this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::UniquePlayerName(_) => "UNIQUE_PLAYER_NAME",
+            GameError::UniqueGuess(_) => "UNIQUE_GUESS",
+            GameError::UniqueSpymaster(_) => "UNIQUE_SPYMASTER",
+            GameError::PlayerNotFound(_) => "PLAYER_NOT_FOUND",
+            GameError::WrongTeam(_) => "WRONG_TEAM",
+            GameError::NotASpymaster(_) => "NOT_A_SPYMASTER",
+            GameError::NotAnOperative(_) => "NOT_AN_OPERATIVE",
+            GameError::NotATeammate(_) => "NOT_A_TEAMMATE",
+            GameError::InvalidGuess(_) => "INVALID_GUESS",
+            GameError::InvalidName(_) => "INVALID_NAME",
+            GameError::InvalidWordList(_) => "INVALID_WORD_LIST",
+            GameError::InvalidSettings(_) => "INVALID_SETTINGS",
+            GameError::TurnStarted => "TURN_STARTED",
+            GameError::TurnPending => "TURN_PENDING",
+            GameError::TeamIncomplete(_) => "TEAM_INCOMPLETE",
+            GameError::GameNotStarted => "GAME_NOT_STARTED",
+            GameError::GameStarted => "GAME_STARTED",
+            GameError::NotTheHost(_) => "NOT_THE_HOST",
+            GameError::PlayersNotReady(_) => "PLAYERS_NOT_READY",
+            GameError::NothingToUndo => "NOTHING_TO_UNDO",
+            GameError::UndoExpired => "UNDO_EXPIRED",
+            GameError::NotTheActor(_) => "NOT_THE_ACTOR",
+            GameError::ResultRecorded => "RESULT_RECORDED",
+            GameError::RerollClosed => "REROLL_CLOSED",
+            GameError::RerollLimit(_) => "REROLL_LIMIT",
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            GameError::UniquePlayerName(u)
+            | GameError::UniqueGuess(u)
+            | GameError::UniqueSpymaster(u) => json!(u),
+            GameError::PlayerNotFound(name)
+            | GameError::WrongTeam(name)
+            | GameError::NotASpymaster(name)
+            | GameError::NotAnOperative(name)
+            | GameError::NotATeammate(name)
+            | GameError::NotTheHost(name)
+            | GameError::NotTheActor(name) => json!({ "player": name }),
+            GameError::PlayersNotReady(names) => json!({ "players": names }),
+            GameError::InvalidGuess(msg)
+            | GameError::InvalidName(msg)
+            | GameError::InvalidWordList(msg)
+            | GameError::InvalidSettings(msg) => {
+                json!({ "reason": msg })
+            }
+            GameError::TeamIncomplete(team) => json!({ "team": team }),
+            GameError::RerollLimit(limit) => json!({ "limit": limit }),
+            GameError::TurnStarted
+            | GameError::TurnPending
+            | GameError::GameNotStarted
+            | GameError::GameStarted
+            | GameError::NothingToUndo
+            | GameError::UndoExpired
+            | GameError::ResultRecorded
+            | GameError::RerollClosed => Value::Null,
+        }
+    }
 }
 
 impl fmt::Display for GameError {
@@ -40,29 +128,133 @@ impl fmt::Display for GameError {
         match self {
             GameError::UniquePlayerName(u) => u.fmt(f),
             GameError::UniqueGuess(u) => u.fmt(f),
+            GameError::UniqueSpymaster(u) => u.fmt(f),
             GameError::PlayerNotFound(name) => write!(f, "{} is not a player in the game", name),
             GameError::WrongTeam(name) =>  write!(f, "{}'s team is not up", name),
             GameError::NotASpymaster(name) =>  write!(f, "{} is not a spy master", name),
             GameError::NotAnOperative(name) =>  write!(f, "{} is not an operative", name),
+            GameError::NotATeammate(name) => write!(f, "{} is not on the same team", name),
             GameError::InvalidGuess(msg) => write!(
                 f,
                 "Guess must be made when a turn is in progress by an operative on the correct team: {}",
                 msg
             ),
+            GameError::InvalidName(msg) => write!(f, "Invalid name: {}", msg),
+            GameError::InvalidWordList(msg) => write!(f, "Invalid word list: {}", msg),
+            GameError::InvalidSettings(msg) => write!(f, "Invalid settings: {}", msg),
             GameError::TurnStarted => write!(f, "turn is already started"),
-            GameError::TurnPending => write!(f, "turn is not started")
+            GameError::TurnPending => write!(f, "turn is not started"),
+            GameError::TeamIncomplete(team) => write!(
+                f,
+                "{} team must have a spymaster and at least one operative",
+                team
+            ),
+            GameError::GameNotStarted => write!(f, "game is still in the lobby"),
+            GameError::GameStarted => write!(f, "game is already started"),
+            GameError::NotTheHost(name) => write!(f, "{} is not the host", name),
+            GameError::PlayersNotReady(names) => {
+                write!(f, "players are not ready: {}", names.join(", "))
+            }
+            GameError::NothingToUndo => write!(f, "there is no clue or guess to undo"),
+            GameError::UndoExpired => write!(f, "the last clue or guess can no longer be undone"),
+            GameError::NotTheActor(name) => write!(
+                f,
+                "{} neither made the last clue or guess nor is the host",
+                name
+            ),
+            GameError::ResultRecorded => write!(
+                f,
+                "the daily challenge is over and its result is recorded, so it cannot be undone"
+            ),
+            GameError::RerollClosed => {
+                write!(f, "cards can only be rerolled before the first clue")
+            }
+            GameError::RerollLimit(limit) => {
+                write!(f, "no more than {} cards can be rerolled", limit)
+            }
         }
     }
 }
 
 impl From<GameError> for ServiceError {
     fn from(game_error: GameError) -> Self {
+        let body = ErrorBody::new(game_error.code(), game_error.to_string())
+            .with_details(game_error.details());
         match game_error {
-            GameError::UniquePlayerName(ue) => ServiceError::BadRequest(ue.to_string()),
-            GameError::UniqueGuess(ue) => ServiceError::BadRequest(ue.to_string()),
-            e => ServiceError::BadRequest(e.to_string()),
+            GameError::UniquePlayerName(_)
+            | GameError::UniqueGuess(_)
+            | GameError::UniqueSpymaster(_)
+            | GameError::TurnStarted
+            | GameError::TurnPending
+            | GameError::TeamIncomplete(_)
+            | GameError::GameNotStarted
+            | GameError::GameStarted
+            | GameError::PlayersNotReady(_)
+            | GameError::NothingToUndo
+            | GameError::UndoExpired
+            | GameError::ResultRecorded
+            | GameError::RerollClosed
+            | GameError::RerollLimit(_) => ServiceError::Conflict(body),
+            GameError::PlayerNotFound(_) => ServiceError::NotFound(body),
+            GameError::WrongTeam(_)
+            | GameError::NotASpymaster(_)
+            | GameError::NotAnOperative(_)
+            | GameError::NotTheHost(_)
+            | GameError::NotTheActor(_) => ServiceError::Forbidden(body),
+            GameError::NotATeammate(_)
+            | GameError::InvalidGuess(_)
+            | GameError::InvalidName(_)
+            | GameError::InvalidWordList(_)
+            | GameError::InvalidSettings(_) => ServiceError::Unprocessable(body),
         }
     }
 }
 
 impl Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{game::model::GameError, ServiceError};
+
+    #[test]
+    fn unique_guess_is_a_conflict() {
+        match GameError::unique_guess(3).into() {
+            ServiceError::Conflict(body) => {
+                assert_eq!(body.code, "UNIQUE_GUESS");
+                assert_eq!(body.details["value"], json!("3"));
+            }
+            e => panic!("expected a conflict, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn wrong_team_is_forbidden() {
+        match GameError::WrongTeam("foo".to_string()).into() {
+            ServiceError::Forbidden(body) => {
+                assert_eq!(body.code, "WRONG_TEAM");
+                assert_eq!(body.details, json!({ "player": "foo" }));
+            }
+            e => panic!("expected forbidden, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn player_not_found_is_not_found() {
+        match GameError::PlayerNotFound("foo".to_string()).into() {
+            ServiceError::NotFound(body) => {
+                assert_eq!(body.code, "PLAYER_NOT_FOUND");
+                assert_eq!(body.details, json!({ "player": "foo" }));
+            }
+            e => panic!("expected not found, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn invalid_guess_is_unprocessable() {
+        let error: ServiceError = GameError::InvalidGuess("bar".to_string()).into();
+        assert!(matches!(error, ServiceError::Unprocessable(_)));
+        assert_eq!(error.body().code, "INVALID_GUESS");
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    key_card::KeyCard,
+    model::{Board, GameSettings, Player, Team},
+};
+
+/// Something that happened in a game. Folding a game's events in order rebuilds the game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    Created {
+        name: String,
+        board: Box<Board>,
+        first_team: Team,
+        #[serde(default)]
+        settings: GameSettings,
+        #[serde(default)]
+        seed: Option<u64>,
+        #[serde(default)]
+        key_card: Option<KeyCard>,
+    },
+    PlayerJoined {
+        player: Player,
+    },
+    PlayerLeft {
+        player_name: String,
+    },
+    PlayerReady {
+        player_name: String,
+        ready: bool,
+    },
+    GameStarted {
+        player_name: String,
+    },
+    PlayerSwitched {
+        player_name: String,
+        team: Team,
+        spymaster_secret: Option<String>,
+    },
+    SpymasterHandedOff {
+        spymaster_name: String,
+        teammate_name: String,
+        spymaster_secret: String,
+    },
+    ClueGiven {
+        spymaster_name: String,
+        clue: (String, usize),
+    },
+    GuessMade {
+        player_name: String,
+        board_index: usize,
+    },
+    /// Replaces the word on a card before the first clue, keeping its color.
+    CardRerolled {
+        player_name: String,
+        board_index: usize,
+        word: String,
+    },
+    /// Takes back the latest clue or guess of the current turn.
+    Undone {
+        player_name: String,
+    },
+    TurnEnded,
+    /// Recorded after the guess that decided the game; does not change the game by itself.
+    GameEnded {
+        winner: Team,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameEvent {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl GameEvent {
+    pub fn now(event: Event) -> Self {
+        Self {
+            at: Utc::now(),
+            event,
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::model::{GameError, Player, Team, Turn, TurnData},
+    game::model::{
+        validate_board_index, validate_player_name, Deadline, GameError, GameSettings, Guess,
+        Phase, Player, Team, Turn, TurnData,
+    },
     Lowercase,
 };
 
@@ -12,6 +16,25 @@ pub struct GameInfo {
     name: String,
     players: HashMap<Lowercase, Player>,
     turns: Vec<Turn>,
+    #[serde(default)]
+    host: Option<Lowercase>,
+    #[serde(default)]
+    phase: Phase,
+    /// Incremented each time the game is saved, so clients can tell whether it has changed.
+    #[serde(default)]
+    version: u64,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    settings: GameSettings,
+    /// When the current phase of the current turn times out, if the settings limit it.
+    #[serde(default)]
+    turn_deadline: Option<Deadline>,
+    /// The day this game plays the board of, if it is a daily challenge.
+    #[serde(default)]
+    daily: Option<NaiveDate>,
 }
 
 impl GameInfo {
@@ -20,6 +43,54 @@ impl GameInfo {
             name,
             players: HashMap::new(),
             turns: vec![Turn::Pending(first_team)],
+            host: None,
+            phase: Phase::Started,
+            version: 0,
+            created_at: None,
+            updated_at: None,
+            settings: GameSettings::default(),
+            turn_deadline: None,
+            daily: None,
+        }
+    }
+
+    /// Applies the options the game is created with, opening the lobby if they ask for one.
+    pub fn with_settings(self, settings: GameSettings) -> Self {
+        let phase = if settings.lobby {
+            Phase::lobby()
+        } else {
+            Phase::Started
+        };
+        Self {
+            settings,
+            phase,
+            ..self
+        }
+    }
+
+    pub fn settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    pub fn daily(&self) -> Option<&NaiveDate> {
+        self.daily.as_ref()
+    }
+
+    pub fn with_daily(self, date: NaiveDate) -> Self {
+        Self {
+            daily: Some(date),
+            ..self
+        }
+    }
+
+    pub fn turn_deadline(&self) -> Option<&Deadline> {
+        self.turn_deadline.as_ref()
+    }
+
+    pub fn with_turn_deadline(self, turn_deadline: Option<Deadline>) -> Self {
+        Self {
+            turn_deadline,
+            ..self
         }
     }
 
@@ -35,6 +106,145 @@ impl GameInfo {
         self.players.get(&Lowercase::new(player_name))
     }
 
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn next_version(self) -> Self {
+        Self {
+            version: self.version + 1,
+            ..self
+        }
+    }
+
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+
+    pub fn created(self, at: DateTime<Utc>) -> Self {
+        Self {
+            created_at: Some(at),
+            updated_at: Some(at),
+            ..self
+        }
+    }
+
+    pub fn touch(self, at: DateTime<Utc>) -> Self {
+        Self {
+            updated_at: Some(at),
+            ..self
+        }
+    }
+
+    /// Records when the latest clue or guess of the current turn was made.
+    pub fn timestamp_latest(self, at: DateTime<Utc>) -> Self {
+        let turn = match self.current_turn() {
+            Turn::Pending(_) => return self,
+            Turn::Started(data) if data.guesses.is_empty() => TurnData {
+                clue_given_at: Some(at),
+                ..data.clone()
+            },
+            Turn::Started(data) => {
+                let mut data = data.clone();
+                data.guesses[0].at = Some(at);
+                data
+            }
+        };
+        Self {
+            turns: [vec![Turn::Started(turn)], self.turns[1..].to_vec()].concat(),
+            ..self
+        }
+    }
+
+    pub fn host(&self) -> Option<&Player> {
+        self.host.as_ref().and_then(|key| self.players.get(key))
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.phase == Phase::Started
+    }
+
+    pub fn set_ready(self, player_name: &str, ready: bool) -> Result<Self, GameError> {
+        let key = Lowercase::new(player_name);
+        if !self.players.contains_key(&key) {
+            return Err(GameError::PlayerNotFound(player_name.to_string()));
+        }
+        match &self.phase {
+            Phase::Started => Err(GameError::GameStarted),
+            Phase::Lobby(ready_players) => {
+                let ready_players = ready_players
+                    .iter()
+                    .filter(|&k| k != &key)
+                    .cloned()
+                    .chain(if ready { Some(key.clone()) } else { None })
+                    .collect();
+                Ok(Self {
+                    phase: Phase::Lobby(ready_players),
+                    ..self.clone()
+                })
+            }
+        }
+    }
+
+    /// Ends the lobby phase. Only the host can start the game, once every player is ready and
+    /// both teams are complete.
+    pub fn start_game(self, player_name: &str) -> Result<Self, GameError> {
+        let ready_players = match &self.phase {
+            Phase::Started => return Err(GameError::GameStarted),
+            Phase::Lobby(ready_players) => ready_players,
+        };
+        if self.host != Some(Lowercase::new(player_name)) {
+            return Err(GameError::NotTheHost(player_name.to_string()));
+        }
+
+        let mut not_ready: Vec<String> = self
+            .players
+            .iter()
+            .filter(|(k, _)| !ready_players.contains(k))
+            .map(|(_, p)| p.name.clone())
+            .collect();
+        if !not_ready.is_empty() {
+            not_ready.sort();
+            return Err(GameError::PlayersNotReady(not_ready));
+        }
+        self.check_teams()?;
+
+        Ok(Self {
+            phase: Phase::Started,
+            ..self
+        })
+    }
+
+    pub fn spymaster(&self, team: &Team) -> Option<&Player> {
+        self.players
+            .values()
+            .find(|p| &p.team == team && p.spymaster_secret.is_some())
+    }
+
+    pub fn operatives(&self, team: &Team) -> Vec<&Player> {
+        self.players
+            .values()
+            .filter(|p| &p.team == team && p.spymaster_secret.is_none())
+            .collect()
+    }
+
+    /// Each team needs exactly one spymaster and at least one operative before the first clue.
+    pub fn check_teams(&self) -> Result<(), GameError> {
+        [Team::Blue, Team::Red]
+            .iter()
+            .find(|&team| self.spymaster(team).is_none() || self.operatives(team).is_empty())
+            .map(|&team| Err(GameError::TeamIncomplete(team)))
+            .unwrap_or(Ok(()))
+    }
+
     pub fn turns(&self) -> &[Turn] {
         &self.turns
     }
@@ -45,14 +255,19 @@ impl GameInfo {
             .expect("Encountered GameInfo with empty list of Turns. This should never happen...")
     }
 
+    pub fn clue_given(&self) -> bool {
+        self.turns.iter().any(|t| matches!(t, Turn::Started(_)))
+    }
+
     pub fn guesses(&self) -> Vec<(Player, usize)> {
         self.turns
             .iter()
             .filter_map(|t| match t {
-                Turn::Started(data) => Some(data.guesses.clone()),
+                Turn::Started(data) => Some(data.guesses.iter()),
                 _ => None,
             })
             .flatten()
+            .map(|guess| (guess.player.clone(), guess.index))
             .collect()
     }
 
@@ -66,6 +281,8 @@ impl GameInfo {
         let previous_turns = self.turns[1..].to_vec();
 
         match (maybe_player, current_turn) {
+            _ if !self.is_started() => Err(GameError::GameNotStarted),
+
             (_, Turn::Started(_)) => Err(GameError::TurnStarted),
 
             (None, _) => Err(GameError::PlayerNotFound(spymaster_name)),
@@ -82,14 +299,19 @@ impl GameInfo {
                 Err(GameError::WrongTeam(spymaster_name))
             }
 
-            (Some(player), _) => Ok(Self {
-                turns: [
-                    vec![Turn::Started(TurnData::new(player.clone(), clue))],
-                    previous_turns,
-                ]
-                .concat(),
-                ..self.clone()
-            }),
+            (Some(player), _) => {
+                if !self.clue_given() {
+                    self.check_teams()?;
+                }
+                Ok(Self {
+                    turns: [
+                        vec![Turn::Started(TurnData::new(player.clone(), clue))],
+                        previous_turns,
+                    ]
+                    .concat(),
+                    ..self.clone()
+                })
+            }
         }
     }
 
@@ -117,11 +339,23 @@ impl GameInfo {
     }
 
     pub fn add_player(self, player: Player) -> Result<Self, GameError> {
+        let player = Player {
+            name: validate_player_name(&player.name)?,
+            ..player
+        };
         let key = Lowercase::new(player.name.as_str());
         if self.players.contains_key(&key) {
             return Err(GameError::unique_player(player.name));
         }
+        if let (Some(_), Some(spymaster)) = (&player.spymaster_secret, self.spymaster(&player.team))
+        {
+            return Err(GameError::unique_spymaster(
+                player.team,
+                spymaster.name.clone(),
+            ));
+        }
         Ok(Self {
+            host: self.host.clone().or_else(|| Some(key.clone())),
             players: [
                 self.players.clone().into_iter().collect(),
                 vec![(key, player)],
@@ -138,24 +372,180 @@ impl GameInfo {
         if !self.players.contains_key(&key) {
             return Err(GameError::PlayerNotFound(player_name.to_string()));
         }
+        let players: HashMap<Lowercase, Player> = self
+            .players
+            .clone()
+            .into_iter()
+            .filter(|(k, _)| k.clone() != key)
+            .collect();
+        let host = match &self.host {
+            Some(host) if host == &key => players
+                .keys()
+                .min_by(|a, b| a.value().cmp(b.value()))
+                .cloned(),
+            host => host.clone(),
+        };
+        let phase = match &self.phase {
+            Phase::Lobby(ready) => {
+                Phase::Lobby(ready.iter().filter(|&k| k != &key).cloned().collect())
+            }
+            Phase::Started => Phase::Started,
+        };
+        Ok(Self {
+            players,
+            host,
+            phase,
+            ..self.clone()
+        })
+    }
+
+    /// Moves a player to another team and/or role, keeping their name (and so their guess history).
+    /// Nobody can join or leave the team whose turn is in progress until it ends, so an operative
+    /// cannot guess for both sides in the same round.
+    pub fn switch_player(
+        self,
+        player_name: &str,
+        team: Team,
+        spymaster_secret: Option<String>,
+    ) -> Result<Self, GameError> {
+        let player = self
+            .player(player_name)
+            .ok_or_else(|| GameError::PlayerNotFound(player_name.to_string()))?;
+
+        if let Turn::Started(TurnData { spymaster, .. }) = self.current_turn() {
+            if player.team == spymaster.team || team == spymaster.team {
+                return Err(GameError::TurnStarted);
+            }
+        }
+        if let (Some(_), Some(spymaster)) = (&spymaster_secret, self.spymaster(&team)) {
+            if spymaster.name != player.name {
+                return Err(GameError::unique_spymaster(team, spymaster.name.clone()));
+            }
+        }
+
+        let switched = Player {
+            team,
+            spymaster_secret,
+            ..player.clone()
+        };
+        let phase = match &self.phase {
+            Phase::Lobby(ready) => Phase::Lobby(
+                ready
+                    .iter()
+                    .filter(|&k| k != &Lowercase::new(player_name))
+                    .cloned()
+                    .collect(),
+            ),
+            Phase::Started => Phase::Started,
+        };
+
+        Ok(Self {
+            phase,
+            players: self
+                .players
+                .clone()
+                .into_iter()
+                .chain(vec![(Lowercase::new(switched.name.as_str()), switched)])
+                .collect(),
+            ..self.clone()
+        })
+    }
+
+    /// Makes an operative the spymaster of their team, and the current spymaster an operative.
+    /// Not allowed while the team's turn is in progress.
+    pub fn hand_off_spymaster(
+        self,
+        spymaster_name: &str,
+        teammate_name: &str,
+        spymaster_secret: String,
+    ) -> Result<Self, GameError> {
+        let spymaster = self
+            .player(spymaster_name)
+            .ok_or_else(|| GameError::PlayerNotFound(spymaster_name.to_string()))?;
+        let teammate = self
+            .player(teammate_name)
+            .ok_or_else(|| GameError::PlayerNotFound(teammate_name.to_string()))?;
+
+        if spymaster.spymaster_secret.is_none() {
+            return Err(GameError::NotASpymaster(spymaster.name.clone()));
+        }
+        if teammate.spymaster_secret.is_some() {
+            return Err(GameError::NotAnOperative(teammate.name.clone()));
+        }
+        if teammate.team != spymaster.team {
+            return Err(GameError::NotATeammate(teammate.name.clone()));
+        }
+        if let Turn::Started(TurnData {
+            spymaster: Player { team, .. },
+            ..
+        }) = self.current_turn()
+        {
+            if team == &spymaster.team {
+                return Err(GameError::TurnStarted);
+            }
+        }
+
+        let updated = [
+            Player {
+                spymaster_secret: None,
+                ..spymaster.clone()
+            },
+            Player {
+                spymaster_secret: Some(spymaster_secret),
+                ..teammate.clone()
+            },
+        ];
+
         Ok(Self {
             players: self
                 .players
                 .clone()
                 .into_iter()
-                .filter(|(k, _)| k.clone() != key)
+                .chain(
+                    updated
+                        .into_iter()
+                        .map(|p| (Lowercase::new(p.name.as_str()), p)),
+                )
                 .collect(),
             ..self.clone()
         })
     }
 
+    /// Takes back the latest clue or guess of the current turn, the inverse of `start_turn` or
+    /// `add_guess`. Only the player who made it, or the host, can take it back.
+    pub fn undo(self, player_name: &str) -> Result<Self, GameError> {
+        let (actor, turn) = match self.current_turn() {
+            Turn::Pending(_) => return Err(GameError::NothingToUndo),
+            Turn::Started(data) => match data.guesses.split_first() {
+                Some((latest, guesses)) => (
+                    &latest.player,
+                    Turn::Started(TurnData {
+                        guesses: guesses.to_vec(),
+                        ..data.clone()
+                    }),
+                ),
+                None => (&data.spymaster, Turn::Pending(data.spymaster.team)),
+            },
+        };
+        let key = Lowercase::new(player_name);
+        if Lowercase::new(actor.name.as_str()) != key && self.host.as_ref() != Some(&key) {
+            return Err(GameError::NotTheActor(player_name.to_string()));
+        }
+        Ok(Self {
+            turns: [vec![turn], self.turns[1..].to_vec()].concat(),
+            ..self.clone()
+        })
+    }
+
     pub fn add_guess(self, guess: (&str, usize)) -> Result<Self, GameError> {
         let (player_name, board_index) = guess;
+        let board_index = validate_board_index(board_index)?;
         let player = self.players.get(&Lowercase::new(player_name));
         let head = self.current_turn();
         let tail = self.turns[1..].to_vec();
 
         match (player, head) {
+            _ if !self.is_started() => Err(GameError::GameNotStarted),
             (_, Turn::Pending(_)) => Err(GameError::TurnPending),
             (None, _) => Err(GameError::PlayerNotFound(player_name.to_string())),
             (Some(player), Turn::Started(TurnData { spymaster, .. }))
@@ -171,19 +561,15 @@ impl GameInfo {
                 }),
                 _,
             ) => Err(GameError::NotAnOperative(name.clone())),
-            (
-                Some(player),
-                Turn::Started(TurnData {
-                    clue,
-                    guesses,
-                    spymaster,
-                }),
-            ) => Ok(Self {
+            (Some(player), Turn::Started(data)) => Ok(Self {
                 turns: [
                     vec![Turn::Started(TurnData {
-                        guesses: [vec![(player.clone(), board_index)], guesses.clone()].concat(),
-                        clue: clue.clone(),
-                        spymaster: spymaster.clone(),
+                        guesses: [
+                            vec![Guess::new(player.clone(), board_index)],
+                            data.guesses.clone(),
+                        ]
+                        .concat(),
+                        ..data.clone()
                     })],
                     tail,
                 ]
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::{
-    game::model::{GameError, GameInfo, Player, Team, Turn, TurnData},
+    game::model::{GameError, GameInfo, GameSettings, Guess, Phase, Player, Team, Turn, TurnData},
     Lowercase,
 };
 
@@ -19,10 +19,42 @@ fn test_player(team: Team, is_spymaster: bool) -> Player {
     }
 }
 
+fn test_roster() -> HashMap<Lowercase, Player> {
+    vec![
+        test_player(Team::Blue, true),
+        Player {
+            name: "bar".to_string(),
+            ..test_player(Team::Blue, false)
+        },
+        Player {
+            name: "baz".to_string(),
+            ..test_player(Team::Red, true)
+        },
+        Player {
+            name: "buzz".to_string(),
+            ..test_player(Team::Red, false)
+        },
+    ]
+    .into_iter()
+    .map(|p| (Lowercase::new(p.name.as_str()), p))
+    .collect()
+}
+
 fn test_clue() -> (String, usize) {
     (TEST_NAME.to_string(), 1)
 }
 
+fn test_started_info() -> GameInfo {
+    GameInfo::new(TEST_NAME.to_string(), Team::Blue)
+}
+
+fn test_lobby_info() -> GameInfo {
+    GameInfo::new(TEST_NAME.to_string(), Team::Blue).with_settings(GameSettings {
+        lobby: true,
+        ..GameSettings::default()
+    })
+}
+
 fn test_turn_data() -> TurnData {
     TurnData::new(test_player(Team::Blue, false), test_clue())
 }
@@ -32,22 +64,31 @@ fn new() {
     let info = GameInfo::new(TEST_NAME.to_string(), Team::Blue);
     assert_eq!(info.players, HashMap::new());
     assert_eq!(info.turns, vec![Turn::Pending(Team::Blue)]);
+    assert_eq!(info.phase, Phase::Started);
+    assert_eq!(test_lobby_info().phase, Phase::lobby());
 }
 
 #[test]
 fn start_turn_in_progress() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         players: HashMap::new(),
         turns: vec![Turn::Started(test_turn_data())],
+        ..test_started_info()
     };
     let result = info.start_turn(TEST_NAME.to_string(), test_clue());
     assert_eq!(result.err().unwrap(), GameError::TurnStarted);
 }
 
+#[test]
+fn start_turn_in_lobby() {
+    let info = test_lobby_info();
+    let result = info.start_turn(TEST_NAME.to_string(), test_clue());
+    assert_eq!(result.err().unwrap(), GameError::GameNotStarted);
+}
+
 #[test]
 fn start_turn_player_not_found() {
-    let info = GameInfo::new(TEST_NAME.to_string(), Team::Blue);
+    let info = test_started_info();
     let result = info.start_turn(TEST_NAME.to_string(), test_clue());
     assert_eq!(
         result.err().unwrap(),
@@ -58,12 +99,12 @@ fn start_turn_player_not_found() {
 #[test]
 fn start_turn_wrong_team() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         turns: vec![Turn::Pending(Team::Blue)],
         players: [(Lowercase::new(TEST_NAME), test_player(Team::Red, true))]
             .iter()
             .cloned()
             .collect(),
+        ..test_started_info()
     };
     let result = info.start_turn(TEST_NAME.to_string(), test_clue());
     assert_eq!(
@@ -75,12 +116,12 @@ fn start_turn_wrong_team() {
 #[test]
 fn start_turn_not_a_spymaster() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         turns: vec![Turn::Pending(Team::Blue)],
         players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, false))]
             .iter()
             .cloned()
             .collect(),
+        ..test_started_info()
     };
     let result = info.start_turn(TEST_NAME.to_string(), test_clue());
     assert_eq!(
@@ -89,15 +130,27 @@ fn start_turn_not_a_spymaster() {
     );
 }
 
+#[test]
+fn start_turn_team_incomplete() {
+    let info = GameInfo {
+        turns: vec![Turn::Pending(Team::Blue)],
+        players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, true))]
+            .iter()
+            .cloned()
+            .collect(),
+        ..test_started_info()
+    };
+    let result = info.start_turn(TEST_NAME.to_string(), test_clue());
+    assert_eq!(result.err().unwrap(), GameError::TeamIncomplete(Team::Blue));
+}
+
 #[test]
 fn start_turn() {
     let player = test_player(Team::Blue, true);
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         turns: vec![Turn::Pending(Team::Blue)],
-        players: vec![(Lowercase::new(TEST_NAME), player.clone())]
-            .into_iter()
-            .collect(),
+        players: test_roster(),
+        ..test_started_info()
     };
     let result = info.clone().start_turn(TEST_NAME.to_string(), test_clue());
     assert_eq!(
@@ -115,9 +168,9 @@ fn end_pending_turn() {
     assert_eq!(
         info.end_turn(),
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: HashMap::new(),
             turns: vec![Turn::Pending(Team::Red), Turn::Pending(Team::Blue)],
+            ..test_started_info()
         }
     )
 }
@@ -125,16 +178,16 @@ fn end_pending_turn() {
 #[test]
 fn end_in_progress_turn() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         players: HashMap::new(),
         turns: vec![Turn::Started(test_turn_data())],
+        ..test_started_info()
     };
     assert_eq!(
         info.end_turn(),
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: HashMap::new(),
             turns: vec![Turn::Pending(Team::Red), Turn::Started(test_turn_data())],
+            ..test_started_info()
         }
     )
 }
@@ -142,20 +195,20 @@ fn end_in_progress_turn() {
 #[test]
 fn end_second_turn() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         players: HashMap::new(),
         turns: vec![Turn::Pending(Team::Red), Turn::Started(test_turn_data())],
+        ..test_started_info()
     };
     assert_eq!(
         info.end_turn(),
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: HashMap::new(),
             turns: vec![
                 Turn::Pending(Team::Blue),
                 Turn::Pending(Team::Red),
                 Turn::Started(test_turn_data())
             ],
+            ..test_started_info()
         }
     )
 }
@@ -163,12 +216,12 @@ fn end_second_turn() {
 #[test]
 fn add_duplicate_player() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, false))]
             .iter()
             .cloned()
             .collect(),
         turns: vec![Turn::Pending(Team::Red)],
+        ..test_started_info()
     };
     assert_eq!(
         info.add_player(test_player(Team::Red, true)).err().unwrap(),
@@ -179,12 +232,13 @@ fn add_duplicate_player() {
 #[test]
 fn add_player() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
+        host: Some(Lowercase::new(TEST_NAME)),
         players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, false))]
             .iter()
             .cloned()
             .collect(),
         turns: vec![Turn::Pending(Team::Red)],
+        ..test_started_info()
     };
     let new_player = Player {
         team: Team::Blue,
@@ -220,10 +274,7 @@ pub fn remove_player_not_found() {
 #[test]
 pub fn add_guess_pending_turn() {
     assert_eq!(
-        GameInfo::new(TEST_NAME.to_string(), Team::Blue)
-            .add_guess((TEST_NAME, 0))
-            .err()
-            .unwrap(),
+        test_started_info().add_guess((TEST_NAME, 0)).err().unwrap(),
         GameError::TurnPending
     )
 }
@@ -232,9 +283,9 @@ pub fn add_guess_pending_turn() {
 pub fn add_guess_player_not_found() {
     assert_eq!(
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: HashMap::new(),
             turns: vec![Turn::Started(test_turn_data())],
+            ..test_started_info()
         }
         .add_guess((TEST_NAME, 0))
         .err()
@@ -247,12 +298,12 @@ pub fn add_guess_player_not_found() {
 pub fn add_guess_wrong_team() {
     assert_eq!(
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: [(Lowercase::new(TEST_NAME), test_player(Team::Red, false))]
                 .iter()
                 .cloned()
                 .collect(),
             turns: vec![Turn::Started(test_turn_data())],
+            ..test_started_info()
         }
         .add_guess((TEST_NAME, 0))
         .err()
@@ -265,12 +316,12 @@ pub fn add_guess_wrong_team() {
 pub fn add_guess_not_an_operative() {
     assert_eq!(
         GameInfo {
-            name: TEST_NAME.to_string(),
             players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, true))]
                 .iter()
                 .cloned()
                 .collect(),
             turns: vec![Turn::Started(test_turn_data())],
+            ..test_started_info()
         }
         .add_guess((TEST_NAME, 0))
         .err()
@@ -282,21 +333,304 @@ pub fn add_guess_not_an_operative() {
 #[test]
 pub fn add_guess() {
     let info = GameInfo {
-        name: TEST_NAME.to_string(),
         players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, false))]
             .iter()
             .cloned()
             .collect(),
         turns: vec![Turn::Started(test_turn_data())],
+        ..test_started_info()
     };
     assert_eq!(
         info.clone().add_guess((TEST_NAME, 0)).ok().unwrap(),
         GameInfo {
             turns: vec![Turn::Started(TurnData {
-                guesses: vec![(test_player(Team::Blue, false), 0)],
+                guesses: vec![Guess::new(test_player(Team::Blue, false), 0)],
                 ..test_turn_data()
             })],
             ..info
         }
     )
 }
+
+#[test]
+pub fn add_guess_out_of_range() {
+    let info = GameInfo {
+        players: [(Lowercase::new(TEST_NAME), test_player(Team::Blue, false))]
+            .iter()
+            .cloned()
+            .collect(),
+        turns: vec![Turn::Started(test_turn_data())],
+        ..test_started_info()
+    };
+    assert!(matches!(
+        info.add_guess((TEST_NAME, 999)).err().unwrap(),
+        GameError::InvalidGuess(_)
+    ))
+}
+
+#[test]
+fn add_player_normalizes_name() {
+    let info = GameInfo::new(TEST_NAME.to_string(), Team::Blue)
+        .add_player(Player {
+            name: " ｆｏｏ ".to_string(),
+            ..test_player(Team::Blue, false)
+        })
+        .unwrap();
+    assert_eq!(info.player("FOO").unwrap().name, TEST_NAME);
+    assert_eq!(
+        info.add_player(test_player(Team::Red, false))
+            .err()
+            .unwrap(),
+        GameError::unique_player(TEST_NAME.to_string())
+    )
+}
+
+#[test]
+fn add_player_invalid_name() {
+    assert!(matches!(
+        GameInfo::new(TEST_NAME.to_string(), Team::Blue)
+            .add_player(Player {
+                name: "\u{7}".to_string(),
+                ..test_player(Team::Blue, false)
+            })
+            .err()
+            .unwrap(),
+        GameError::InvalidName(_)
+    ))
+}
+
+#[test]
+fn add_second_spymaster() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    };
+    assert_eq!(
+        info.add_player(Player {
+            name: "qux".to_string(),
+            ..test_player(Team::Blue, true)
+        })
+        .err()
+        .unwrap(),
+        GameError::unique_spymaster(Team::Blue, TEST_NAME.to_string())
+    )
+}
+
+#[test]
+fn hand_off_spymaster() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    };
+    let updated = info
+        .clone()
+        .hand_off_spymaster(TEST_NAME, "bar", "secret".to_string())
+        .unwrap();
+    assert_eq!(updated.spymaster(&Team::Blue).unwrap().name, "bar");
+    assert_eq!(
+        updated.player("bar").unwrap().spymaster_secret,
+        Some("secret".to_string())
+    );
+    assert_eq!(updated.player(TEST_NAME).unwrap().spymaster_secret, None);
+    assert_eq!(
+        info.clone()
+            .hand_off_spymaster(TEST_NAME, "buzz", "secret".to_string())
+            .err()
+            .unwrap(),
+        GameError::NotATeammate("buzz".to_string())
+    );
+    assert_eq!(
+        info.hand_off_spymaster("bar", TEST_NAME, "secret".to_string())
+            .err()
+            .unwrap(),
+        GameError::NotASpymaster("bar".to_string())
+    );
+}
+
+#[test]
+fn hand_off_spymaster_mid_turn() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    }
+    .start_turn(TEST_NAME.to_string(), test_clue())
+    .unwrap();
+    assert_eq!(
+        info.hand_off_spymaster(TEST_NAME, "bar", "secret".to_string())
+            .err()
+            .unwrap(),
+        GameError::TurnStarted
+    );
+}
+
+#[test]
+fn switch_player() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    };
+    let updated = info.clone().switch_player("bar", Team::Red, None).unwrap();
+    assert_eq!(updated.player("bar").unwrap().team, Team::Red);
+    assert_eq!(updated.players().len(), info.players().len());
+    assert_eq!(
+        info.switch_player("bar", Team::Red, Some("secret".to_string()))
+            .err()
+            .unwrap(),
+        GameError::unique_spymaster(Team::Red, "baz".to_string())
+    );
+}
+
+#[test]
+fn switch_active_spymaster() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    }
+    .start_turn(TEST_NAME.to_string(), test_clue())
+    .unwrap();
+    assert_eq!(
+        info.clone()
+            .switch_player(TEST_NAME, Team::Blue, None)
+            .err()
+            .unwrap(),
+        GameError::TurnStarted
+    );
+    assert!(info.switch_player("baz", Team::Red, None).is_ok());
+}
+
+#[test]
+fn switch_teams_mid_turn() {
+    let info = GameInfo {
+        players: test_roster(),
+        turns: vec![Turn::Pending(Team::Blue)],
+        ..test_started_info()
+    }
+    .start_turn(TEST_NAME.to_string(), test_clue())
+    .unwrap();
+    assert_eq!(
+        info.clone()
+            .switch_player("bar", Team::Red, None)
+            .err()
+            .unwrap(),
+        GameError::TurnStarted
+    );
+    assert_eq!(
+        info.clone()
+            .switch_player("buzz", Team::Blue, None)
+            .err()
+            .unwrap(),
+        GameError::TurnStarted
+    );
+    assert!(info
+        .end_turn()
+        .switch_player("bar", Team::Red, None)
+        .is_ok());
+}
+
+#[test]
+fn first_player_is_host() {
+    let info = GameInfo::new(TEST_NAME.to_string(), Team::Blue)
+        .add_player(test_player(Team::Blue, false))
+        .unwrap()
+        .add_player(Player {
+            name: "bar".to_string(),
+            ..test_player(Team::Red, false)
+        })
+        .unwrap();
+    assert_eq!(info.host().unwrap().name, TEST_NAME);
+    let info = info.remove_player(TEST_NAME).unwrap();
+    assert_eq!(info.host().unwrap().name, "bar");
+}
+
+#[test]
+fn start_game() {
+    let info = GameInfo {
+        players: test_roster(),
+        host: Some(Lowercase::new(TEST_NAME)),
+        ..test_lobby_info()
+    };
+    let info = ["bar", "baz"]
+        .iter()
+        .fold(info, |info, &name| info.set_ready(name, true).unwrap());
+    assert_eq!(
+        info.clone().start_game(TEST_NAME).err().unwrap(),
+        GameError::PlayersNotReady(vec!["buzz".to_string(), TEST_NAME.to_string()])
+    );
+    let info = ["buzz", TEST_NAME]
+        .iter()
+        .fold(info, |info, &name| info.set_ready(name, true).unwrap());
+    assert_eq!(
+        info.clone().start_game("bar").err().unwrap(),
+        GameError::NotTheHost("bar".to_string())
+    );
+    let started = info.start_game(TEST_NAME).unwrap();
+    assert!(started.is_started());
+    assert_eq!(
+        started.clone().start_game(TEST_NAME).err().unwrap(),
+        GameError::GameStarted
+    );
+    assert_eq!(
+        started.set_ready(TEST_NAME, false).err().unwrap(),
+        GameError::GameStarted
+    );
+}
+
+#[test]
+fn switch_player_in_lobby_clears_ready() {
+    let info = GameInfo {
+        players: test_roster(),
+        ..test_lobby_info()
+    }
+    .set_ready("bar", true)
+    .unwrap()
+    .switch_player("bar", Team::Red, None)
+    .unwrap();
+    assert_eq!(info.phase, Phase::lobby());
+}
+
+#[test]
+fn undo_is_inverse() {
+    let info = GameInfo {
+        players: test_roster(),
+        host: Some(Lowercase::new("baz")),
+        ..test_started_info()
+    };
+    let started = info
+        .clone()
+        .start_turn(TEST_NAME.to_string(), test_clue())
+        .unwrap();
+    let guessed = started.clone().add_guess(("bar", 0)).unwrap();
+
+    assert_eq!(guessed.undo("bar").unwrap(), started);
+    assert_eq!(started.clone().undo(TEST_NAME).unwrap(), info);
+    assert_eq!(started.undo("baz").unwrap(), info);
+}
+
+#[test]
+fn undo_not_the_actor() {
+    let info = GameInfo {
+        players: test_roster(),
+        host: Some(Lowercase::new(TEST_NAME)),
+        ..test_started_info()
+    }
+    .start_turn(TEST_NAME.to_string(), test_clue())
+    .unwrap()
+    .add_guess(("bar", 0))
+    .unwrap();
+    let result = info.undo("buzz");
+    assert_eq!(
+        result.err().unwrap(),
+        GameError::NotTheActor("buzz".to_string())
+    );
+}
+
+#[test]
+fn undo_pending_turn() {
+    let result = test_started_info().undo(TEST_NAME);
+    assert_eq!(result.err().unwrap(), GameError::NothingToUndo);
+}
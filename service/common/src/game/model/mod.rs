@@ -1,21 +1,32 @@
-use std::convert::TryInto;
+use std::{collections::BTreeSet, convert::TryInto};
 
 pub use board::*;
 pub use card::*;
+use chrono::{DateTime, Duration, Utc};
 pub use error::*;
+pub use event::*;
 pub use info::*;
+pub use phase::*;
 pub use player::*;
 use serde::{Deserialize, Serialize};
+pub use settings::*;
 pub use team::*;
 pub use turn::*;
+pub use validation::*;
+
+use crate::game::key_card::KeyCard;
 
 mod board;
 mod card;
 mod error;
+mod event;
 mod info;
+mod phase;
 mod player;
+mod settings;
 mod team;
 mod turn;
+mod validation;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -36,58 +47,479 @@ pub struct GameData {
     #[serde(flatten)]
     pub info: GameInfo,
     pub board: Board,
+    /// Every event in the game so far, oldest first. Empty for games saved before it was kept.
+    #[serde(default)]
+    pub history: Vec<GameEvent>,
+    /// Words drawn for this game and the earlier games of the same name, so a rematch does not
+    /// deal them again. Kept out of the history.
+    #[serde(default)]
+    pub used_words: BTreeSet<String>,
+    /// Generated the words and colors of the board, together with the dictionaries and used
+    /// words. Only spymasters see it, since it gives the board away.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The physical key card the colors follow, if the board was generated from one. Like the
+    /// seed, it gives the board away.
+    #[serde(default)]
+    pub key_card: Option<KeyCard>,
 }
 
 pub type GameResult = Result<GameData, GameError>;
 
 impl GameData {
-    pub fn new(name: String, board: Board, first_team: Team) -> GameData {
+    pub fn new(name: String, board: Board, first_team: Team, settings: GameSettings) -> GameData {
+        let created = GameEvent::now(Event::Created {
+            name: name.clone(),
+            board: Box::new(board.clone()),
+            first_team,
+            settings: settings.clone(),
+            seed: None,
+            key_card: None,
+        });
         GameData {
-            info: GameInfo::new(name, first_team),
+            info: GameInfo::new(name, first_team)
+                .with_settings(settings)
+                .created(created.at),
             board,
+            history: vec![created],
+            used_words: BTreeSet::new(),
+            seed: None,
+            key_card: None,
+        }
+    }
+
+    pub fn with_used_words(self, used_words: BTreeSet<String>) -> GameData {
+        GameData { used_words, ..self }
+    }
+
+    /// Sets the seed the board was generated from, recording it with the creation of the game so
+    /// it survives a rebuild from the history.
+    pub fn with_seed(self, seed: u64) -> GameData {
+        let mut history = self.history;
+        if let Some(GameEvent {
+            event: Event::Created {
+                seed: created_seed, ..
+            },
+            ..
+        }) = history.first_mut()
+        {
+            *created_seed = Some(seed);
+        }
+        GameData {
+            seed: Some(seed),
+            history,
+            ..self
+        }
+    }
+
+    /// Sets the key card the board follows, recording it with the creation of the game next to
+    /// the seed.
+    pub fn with_key_card(self, key_card: Option<KeyCard>) -> GameData {
+        let mut history = self.history;
+        if let Some(GameEvent {
+            event:
+                Event::Created {
+                    key_card: created_key_card,
+                    ..
+                },
+            ..
+        }) = history.first_mut()
+        {
+            *created_key_card = key_card.clone();
+        }
+        GameData {
+            key_card,
+            history,
+            ..self
+        }
+    }
+
+    /// Rebuilds a game by folding over its events. `None` if the history does not start with the
+    /// game being created.
+    pub fn from_history(history: &[GameEvent]) -> Option<GameResult> {
+        match history.split_first() {
+            Some((
+                created @ GameEvent {
+                    event:
+                        Event::Created {
+                            name,
+                            board,
+                            first_team,
+                            settings,
+                            seed,
+                            key_card,
+                        },
+                    ..
+                },
+                events,
+            )) => {
+                let game = GameData {
+                    info: GameInfo::new(name.clone(), *first_team)
+                        .with_settings(settings.clone())
+                        .created(created.at),
+                    board: *board.clone(),
+                    history: vec![created.clone()],
+                    used_words: BTreeSet::new(),
+                    seed: *seed,
+                    key_card: key_card.clone(),
+                };
+                Some(events.iter().try_fold(game, |game, event| {
+                    let mut game = game.apply(event)?;
+                    game.history.push(event.clone());
+                    Ok(game)
+                }))
+            }
+            _ => None,
         }
     }
 
     pub fn join(self, player: Player) -> GameResult {
-        let GameData { board, info } = self;
-        let info = info.add_player(player)?;
-        Ok(Self { info, board })
+        self.record(Event::PlayerJoined { player })
+    }
+
+    pub fn set_ready(self, player_name: &str, ready: bool) -> GameResult {
+        self.record(Event::PlayerReady {
+            player_name: player_name.to_string(),
+            ready,
+        })
+    }
+
+    pub fn start_game(self, player_name: &str) -> GameResult {
+        self.record(Event::GameStarted {
+            player_name: player_name.to_string(),
+        })
     }
 
     pub fn start_turn(self, spymaster_name: String, clue: (String, usize)) -> GameResult {
-        let GameData { board, info } = self;
-        let info = info.start_turn(spymaster_name, clue)?;
-        Ok(Self { info, board })
+        self.record(Event::ClueGiven {
+            spymaster_name,
+            clue,
+        })
     }
 
     pub fn end_turn(self) -> GameData {
-        let GameData { board, info } = self;
-        Self {
-            info: info.end_turn(),
-            board,
-        }
+        self.record(Event::TurnEnded)
+            .expect("Ending a turn failed. This should never happen...")
+    }
+
+    /// Ends a turn that has run out of time as of its deadline rather than whenever it is noticed,
+    /// so the next team's clock starts when the previous one ran out. If that clock would have run
+    /// out by `now` as well, it starts `now` instead, so a game left idle does not go on ending a
+    /// turn on every read.
+    pub fn expire_turn(self, now: DateTime<Utc>) -> GameData {
+        let next_limit = self.info.settings().clue_time_limit;
+        let at = self
+            .info
+            .turn_deadline()
+            .map(|deadline| deadline.0)
+            .filter(|&missed| {
+                next_limit
+                    .and_then(|seconds| deadline_after(missed, seconds))
+                    .map(|next| next > now)
+                    .unwrap_or(true)
+            })
+            .unwrap_or(now);
+        self.record_at(Event::TurnEnded, at)
+            .expect("Ending a turn failed. This should never happen...")
     }
 
     pub fn leave(self, player_name: &str) -> GameResult {
-        let GameData { board, info } = self;
-        let info = info.remove_player(player_name)?;
-        Ok(Self { info, board })
+        self.record(Event::PlayerLeft {
+            player_name: player_name.to_string(),
+        })
+    }
+
+    pub fn switch_player(
+        self,
+        player_name: &str,
+        team: Team,
+        spymaster_secret: Option<String>,
+    ) -> GameResult {
+        self.record(Event::PlayerSwitched {
+            player_name: player_name.to_string(),
+            team,
+            spymaster_secret,
+        })
+    }
+
+    pub fn hand_off_spymaster(
+        self,
+        spymaster_name: &str,
+        teammate_name: &str,
+        spymaster_secret: String,
+    ) -> GameResult {
+        self.record(Event::SpymasterHandedOff {
+            spymaster_name: spymaster_name.to_string(),
+            teammate_name: teammate_name.to_string(),
+            spymaster_secret,
+        })
     }
 
     pub fn guess(self, guess: (&str, usize)) -> GameResult {
         let (player_name, board_index) = guess;
-        let GameData { info, board } = self;
+        let was_over = self.is_over();
+        let game = self.record(Event::GuessMade {
+            player_name: player_name.to_string(),
+            board_index,
+        })?;
+        match game.winner() {
+            Some(winner) if !was_over => game.record(Event::GameEnded { winner }),
+            _ => Ok(game),
+        }
+    }
+
+    /// Replaces the word on a card with `word`, keeping the card's color.
+    pub fn reroll(self, player_name: &str, board_index: usize, word: String) -> GameResult {
+        self.record(Event::CardRerolled {
+            player_name: player_name.to_string(),
+            board_index,
+            word,
+        })
+    }
 
-        info.guesses()
+    /// Whether the player may replace the word on a card: only before the first clue, and only
+    /// as often as the settings allow.
+    pub fn check_reroll(&self, player_name: &str, board_index: usize) -> Result<(), GameError> {
+        validate_board_index(board_index)?;
+        let rerolls = self
+            .history
             .iter()
-            .find(|&(_, index)| index == &board_index)
-            .map(|_| Err(GameError::unique_guess(board_index)))
-            .unwrap_or(Ok(()))?;
+            .filter(|e| matches!(e.event, Event::CardRerolled { .. }))
+            .count() as u32;
+        let limit = self.info.settings().reroll_limit;
+        match self.info.player(player_name) {
+            None => Err(GameError::PlayerNotFound(player_name.to_string())),
+            Some(_) if self.info.clue_given() => Err(GameError::RerollClosed),
+            Some(_) if rerolls >= limit => Err(GameError::RerollLimit(limit)),
+            Some(_) => Ok(()),
+        }
+    }
 
-        let info = info.clone().add_guess((player_name, board_index))?;
+    /// Takes back the latest clue or guess, as long as it was made less than `window` ago. A
+    /// finished daily challenge has had its result recorded, so it stays finished.
+    pub fn undo(self, player_name: &str, window: Duration) -> GameResult {
+        match self.last_action_at() {
+            _ if self.info.daily().is_some() && self.is_over() => Err(GameError::ResultRecorded),
+            None if matches!(self.info.current_turn(), Turn::Pending(_)) => {
+                Err(GameError::NothingToUndo)
+            }
+            Some(at) if Utc::now() - at <= window => {
+                // The end of the game goes with the guess that decided it.
+                let mut history = self.history;
+                if let Some(GameEvent {
+                    event: Event::GameEnded { .. },
+                    ..
+                }) = history.last()
+                {
+                    history.pop();
+                }
+                GameData { history, ..self }.record(Event::Undone {
+                    player_name: player_name.to_string(),
+                })
+            }
+            _ => Err(GameError::UndoExpired),
+        }
+    }
+
+    /// When the clue or guess that `undo` would take back was made.
+    fn last_action_at(&self) -> Option<DateTime<Utc>> {
+        self.history
+            .iter()
+            .fold(Vec::new(), |actions, e| match e.event {
+                Event::ClueGiven { .. } | Event::GuessMade { .. } => [actions, vec![e.at]].concat(),
+                Event::Undone { .. } => actions[..actions.len().saturating_sub(1)].to_vec(),
+                Event::TurnEnded => Vec::new(),
+                _ => actions,
+            })
+            .last()
+            .cloned()
+    }
 
-        Ok(Self { info, board })
+    /// The team that has won: the one whose cards are all revealed, or the opponent of whoever
+    /// revealed the death card.
+    pub fn winner(&self) -> Option<Team> {
+        let guesses = self.info.guesses();
+        let other = |team: &Team| match team {
+            Team::Blue => Team::Red,
+            Team::Red => Team::Blue,
+        };
+        let death = guesses
+            .iter()
+            .find(|(_, index)| self.board[*index].color == CardColor::Death)
+            .map(|(player, _)| other(&player.team));
+        let all_revealed = |team: &Team| {
+            self.board
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| card.color == CardColor::Team(*team))
+                .all(|(index, _)| guesses.iter().any(|(_, guessed)| guessed == &index))
+        };
+        death.or_else(|| {
+            [Team::Blue, Team::Red]
+                .iter()
+                .find(|&team| all_revealed(team))
+                .cloned()
+        })
+    }
+
+    /// Whether a team has won. Undoing the deciding guess also drops its `GameEnded` event, so a
+    /// replay of the history agrees with the live game.
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// Whether the current phase of the turn has run out of time.
+    pub fn turn_expired(&self, now: DateTime<Utc>) -> bool {
+        !self.is_over()
+            && self
+                .info
+                .turn_deadline()
+                .map(|deadline| now >= deadline.0)
+                .unwrap_or(false)
+    }
+
+    /// When the current phase of the turn began: the clue of a started turn, or else the end of
+    /// the previous turn or the start of the game. `None` for the first clue of a game without a
+    /// lobby, which is never timed.
+    fn phase_started_at(&self) -> Option<DateTime<Utc>> {
+        match self.info.current_turn() {
+            Turn::Started(data) => data.clue_given_at,
+            Turn::Pending(_) => self
+                .history
+                .iter()
+                .rev()
+                .find(|e| matches!(e.event, Event::GameStarted { .. } | Event::TurnEnded))
+                .map(|e| e.at),
+        }
     }
+
+    /// Starts the clock for the current phase of the turn from `started_at`, or stops it if the
+    /// phase is untimed or the game is over.
+    fn reset_deadline(self, started_at: Option<DateTime<Utc>>) -> Self {
+        let settings = self.info.settings();
+        let limit = match self.info.current_turn() {
+            Turn::Pending(_) => settings.clue_time_limit,
+            Turn::Started(_) => settings.guess_time_limit,
+        };
+        let deadline = limit
+            .filter(|_| self.info.is_started() && !self.is_over())
+            .zip(started_at)
+            .and_then(|(seconds, at)| deadline_after(at, seconds))
+            .map(Deadline);
+        GameData {
+            info: self.info.with_turn_deadline(deadline),
+            ..self
+        }
+    }
+
+    /// Applies an event and appends it to the history.
+    fn record(self, event: Event) -> GameResult {
+        self.record_at(event, Utc::now())
+    }
+
+    fn record_at(self, event: Event, at: DateTime<Utc>) -> GameResult {
+        let event = GameEvent { at, event };
+        let mut game = self.apply(&event)?;
+        game.history.push(event);
+        Ok(game)
+    }
+
+    /// Applies an event without recording it.
+    fn apply(self, event: &GameEvent) -> GameResult {
+        if let Event::CardRerolled {
+            player_name,
+            board_index,
+            ..
+        } = &event.event
+        {
+            self.check_reroll(player_name, *board_index)?;
+        }
+        let GameData {
+            info,
+            mut board,
+            history,
+            mut used_words,
+            seed,
+            key_card,
+        } = self;
+        let info = match event.event.clone() {
+            Event::Created { .. } | Event::GameEnded { .. } => info,
+            Event::PlayerJoined { player } => info.add_player(player)?,
+            Event::PlayerLeft { player_name } => info.remove_player(&player_name)?,
+            Event::PlayerReady { player_name, ready } => info.set_ready(&player_name, ready)?,
+            Event::GameStarted { player_name } => info.start_game(&player_name)?,
+            Event::PlayerSwitched {
+                player_name,
+                team,
+                spymaster_secret,
+            } => info.switch_player(&player_name, team, spymaster_secret)?,
+            Event::SpymasterHandedOff {
+                spymaster_name,
+                teammate_name,
+                spymaster_secret,
+            } => info.hand_off_spymaster(&spymaster_name, &teammate_name, spymaster_secret)?,
+            Event::ClueGiven {
+                spymaster_name,
+                clue,
+            } => info
+                .start_turn(spymaster_name, clue)?
+                .timestamp_latest(event.at),
+            Event::GuessMade {
+                player_name,
+                board_index,
+            } => {
+                info.guesses()
+                    .iter()
+                    .find(|&(_, index)| index == &board_index)
+                    .map(|_| Err(GameError::unique_guess(board_index)))
+                    .unwrap_or(Ok(()))?;
+                info.add_guess((&player_name, board_index))?
+                    .timestamp_latest(event.at)
+            }
+            Event::CardRerolled {
+                board_index, word, ..
+            } => {
+                if board.iter().any(|card| card.word == word) {
+                    return Err(GameError::InvalidWordList(format!(
+                        "{} is already on the board",
+                        word
+                    )));
+                }
+                used_words.insert(word.clone());
+                board[board_index].word = word;
+                info
+            }
+            Event::Undone { player_name } => info.undo(&player_name)?,
+            Event::TurnEnded => info.end_turn(),
+        };
+        let game = Self {
+            info: info.touch(event.at),
+            board,
+            history,
+            used_words,
+            seed,
+            key_card,
+        };
+        // Undoing puts back the deadline of the phase it returns to, rather than a fresh one.
+        let reset_at = match event.event {
+            Event::GameStarted { .. } | Event::ClueGiven { .. } | Event::TurnEnded => {
+                Some(Some(event.at))
+            }
+            Event::Undone { .. } => Some(game.phase_started_at()),
+            Event::GuessMade { .. } if game.is_over() => Some(None),
+            _ => None,
+        };
+        Ok(match reset_at {
+            Some(at) => game.reset_deadline(at),
+            None => game,
+        })
+    }
+}
+
+/// When a limit of `seconds` starting `at` runs out, if that is representable.
+fn deadline_after(at: DateTime<Utc>, seconds: u64) -> Option<DateTime<Utc>> {
+    let limit = Duration::from_std(std::time::Duration::from_secs(seconds)).ok()?;
+    at.checked_add_signed(limit)
 }
 
 impl From<(Player, GameData)> for Game {
@@ -106,14 +538,14 @@ impl From<(Player, GameData)> for Game {
     }
 }
 
-impl Into<GameState> for GameData {
-    fn into(self) -> GameState {
-        let cards: Vec<CardState> = self
+impl From<GameData> for GameState {
+    fn from(game: GameData) -> Self {
+        let cards: Vec<CardState> = game
             .board
             .iter()
             .enumerate()
             .map(|(index, card)| {
-                let maybe_card_color = self
+                let maybe_card_color = game
                     .info
                     .guesses()
                     .iter()
@@ -127,7 +559,7 @@ impl Into<GameState> for GameData {
             .collect();
         let board = cards.try_into().unwrap();
         GameState {
-            info: self.info,
+            info: game.info,
             board,
         }
     }
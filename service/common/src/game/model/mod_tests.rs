@@ -1,10 +1,33 @@
 use std::convert::TryInto;
 
-use crate::game::model::{Card, CardColor, GameData, Player, Team, Turn, TurnData};
+use chrono::Duration;
+
+use crate::game::{
+    key_card::KeyCard,
+    model::{
+        Card, CardColor, Event, GameData, GameError, GameSettings, Player, Team, Turn, TurnData,
+    },
+};
 
 fn test_game() -> GameData {
+    test_game_with(GameSettings::default())
+}
+
+/// A game with four players that has been through the lobby and started.
+fn test_game_with(settings: GameSettings) -> GameData {
+    let game = test_lobbyless_game_with(GameSettings {
+        lobby: true,
+        ..settings
+    });
+    ["foo", "bar", "baz", "buzz"]
+        .iter()
+        .fold(game, |game, name| game.set_ready(name, true).unwrap())
+        .start_game("foo")
+        .unwrap()
+}
+
+fn test_lobbyless_game_with(settings: GameSettings) -> GameData {
     let cards: Vec<Card> = (0..25)
-        .into_iter()
         .map(|i| {
             let color: CardColor = match i {
                 blue if blue < 9 => CardColor::Team(Team::Blue),
@@ -19,7 +42,12 @@ fn test_game() -> GameData {
         })
         .collect();
 
-    let game = GameData::new("test".to_string(), cards.try_into().unwrap(), Team::Blue);
+    let game = GameData::new(
+        "test".to_string(),
+        cards.try_into().unwrap(),
+        Team::Blue,
+        settings,
+    );
 
     let players: Vec<Player> = vec![
         Player {
@@ -117,6 +145,23 @@ fn deserialize() {
     );
 }
 
+#[test]
+fn deserialize_untimed_guesses() {
+    let data: TurnData = serde_json::from_str(
+        r#"{
+          "spymaster": { "team": "Blue", "name": "foo", "spymaster_secret": "" },
+          "clue": ["Foo", 1],
+          "guesses": [[{ "team": "Blue", "name": "bar", "spymaster_secret": null }, 3]]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(data.guesses[0].index, 3);
+    assert_eq!(data.guesses[0].at, None);
+
+    let json = serde_json::to_string(&data).unwrap();
+    assert_eq!(serde_json::from_str::<TurnData>(&json).unwrap(), data);
+}
+
 #[test]
 fn guess() {
     let game: GameData = test_game();
@@ -148,6 +193,292 @@ fn guess() {
     assert!(failed_update.is_err())
 }
 
+#[test]
+fn from_history() {
+    let game = test_game()
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap()
+        .guess(("bar", 0))
+        .unwrap()
+        .end_turn()
+        .leave("buzz")
+        .unwrap();
+
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+    assert!(GameData::from_history(&game.history[1..]).is_none());
+}
+
+#[test]
+fn from_history_keeps_seed() {
+    let game = test_game().with_seed(42);
+
+    let rebuilt = GameData::from_history(&game.history).unwrap().unwrap();
+    assert_eq!(rebuilt.seed, Some(42));
+    assert_eq!(rebuilt, game);
+}
+
+#[test]
+fn from_history_keeps_key_card() {
+    let key_card = KeyCard {
+        id: "7".to_string(),
+        rotation: 90,
+    };
+    let game = test_game()
+        .with_seed(42)
+        .with_key_card(Some(key_card.clone()));
+
+    let rebuilt = GameData::from_history(&game.history).unwrap().unwrap();
+    assert_eq!(rebuilt.key_card, Some(key_card));
+    assert_eq!(rebuilt.seed, Some(42));
+    assert_eq!(rebuilt, game);
+}
+
+#[test]
+fn game_ended() {
+    let game = test_game()
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap();
+    assert_eq!(game.winner(), None);
+
+    let game = game.guess(("bar", 17)).unwrap();
+    assert_eq!(game.winner(), Some(Team::Red));
+    assert!(game.is_over());
+    assert_eq!(
+        game.history.last().unwrap().event,
+        Event::GameEnded { winner: Team::Red }
+    );
+}
+
+#[test]
+fn timestamps() {
+    let game = test_game();
+    assert!(game.info.created_at().is_some());
+    assert!(game.info.updated_at() >= game.info.created_at());
+
+    let game = game
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap()
+        .guess(("bar", 0))
+        .unwrap();
+    let at = &game.history.last().unwrap().at;
+    assert_eq!(game.info.updated_at(), Some(at));
+    match game.info.current_turn() {
+        Turn::Started(data) => {
+            assert!(data.clue_given_at <= Some(*at));
+            assert_eq!(data.guesses[0].at, Some(*at));
+        }
+        turn => panic!("expected a started turn, got {:?}", turn),
+    }
+}
+
+#[test]
+fn turn_timers() {
+    let game = test_game_with(GameSettings {
+        clue_time_limit: Some(60),
+        guess_time_limit: Some(30),
+        ..GameSettings::default()
+    });
+    let started_at = game.history.last().unwrap().at;
+    assert_eq!(
+        game.info.turn_deadline().unwrap().0,
+        started_at + Duration::seconds(60)
+    );
+    assert!(!game.turn_expired(started_at));
+    assert!(game.turn_expired(started_at + Duration::seconds(60)));
+
+    let game = game
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap();
+    let clue_at = game.history.last().unwrap().at;
+    assert_eq!(
+        game.info.turn_deadline().unwrap().remaining(clue_at),
+        Duration::seconds(30)
+    );
+
+    let game = game.guess(("bar", 17)).unwrap();
+    assert_eq!(game.info.turn_deadline(), None);
+    assert!(!game.turn_expired(clue_at + Duration::seconds(30)));
+}
+
+#[test]
+fn undo_keeps_turn_timers() {
+    let game = test_game_with(GameSettings {
+        clue_time_limit: Some(60),
+        guess_time_limit: Some(30),
+        ..GameSettings::default()
+    });
+    let pending_deadline = *game.info.turn_deadline().unwrap();
+
+    let clued = game
+        .start_turn("foo".to_string(), ("bar".to_string(), 2))
+        .unwrap();
+    let guess_deadline = *clued.info.turn_deadline().unwrap();
+    let game = clued
+        .guess(("bar", 0))
+        .unwrap()
+        .undo("bar", Duration::seconds(10))
+        .unwrap();
+    assert_eq!(game.info.turn_deadline(), Some(&guess_deadline));
+
+    let game = game.undo("foo", Duration::seconds(10)).unwrap();
+    assert_eq!(game.info.turn_deadline(), Some(&pending_deadline));
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+}
+
+#[test]
+fn turn_timers_out_of_range() {
+    for limit in [1_000_000_000_000_000, u64::MAX] {
+        let game = test_game_with(GameSettings {
+            clue_time_limit: Some(limit),
+            ..GameSettings::default()
+        });
+        assert_eq!(game.info.turn_deadline(), None);
+    }
+}
+
+#[test]
+fn without_lobby() {
+    let game = test_lobbyless_game_with(GameSettings {
+        clue_time_limit: Some(60),
+        guess_time_limit: Some(30),
+        ..GameSettings::default()
+    });
+    assert!(game.info.is_started());
+    assert_eq!(game.info.turn_deadline(), None);
+
+    let game = game
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap();
+    assert!(game.info.turn_deadline().is_some());
+
+    let game = game.undo("foo", Duration::seconds(10)).unwrap();
+    assert_eq!(game.info.turn_deadline(), None);
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+}
+
+#[test]
+fn expired_turn_ends_at_deadline() {
+    let game = test_game_with(GameSettings {
+        clue_time_limit: Some(60),
+        ..GameSettings::default()
+    });
+    let missed = game.info.turn_deadline().unwrap().0;
+
+    let game = game.expire_turn(missed + Duration::seconds(5));
+    assert_eq!(game.info.current_turn(), &Turn::Pending(Team::Red));
+    assert_eq!(game.history.last().unwrap().at, missed);
+    assert_eq!(
+        game.info.turn_deadline().unwrap().0,
+        missed + Duration::seconds(60)
+    );
+}
+
+#[test]
+fn idle_game_ends_one_turn() {
+    let game = test_game_with(GameSettings {
+        clue_time_limit: Some(60),
+        ..GameSettings::default()
+    });
+    let now = game.info.turn_deadline().unwrap().0 + Duration::hours(1);
+    assert!(game.turn_expired(now));
+
+    let game = game.expire_turn(now);
+    assert_eq!(game.info.current_turn(), &Turn::Pending(Team::Red));
+    assert_eq!(game.info.turns().len(), 2);
+    assert_eq!(
+        game.info.turn_deadline().unwrap().0,
+        now + Duration::seconds(60)
+    );
+    assert!(!game.turn_expired(now + Duration::seconds(30)));
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+}
+
+#[test]
+fn undo() {
+    let started = test_game()
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap();
+    let game = started
+        .clone()
+        .guess(("bar", 17))
+        .unwrap()
+        .undo("bar", Duration::seconds(10))
+        .unwrap();
+
+    assert_eq!(game.info.turns(), started.info.turns());
+    assert!(!game.is_over());
+    assert!(!game
+        .history
+        .iter()
+        .any(|e| matches!(e.event, Event::GameEnded { .. })));
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+
+    let game = game.undo("foo", Duration::seconds(10)).unwrap();
+    assert_eq!(
+        game.clone().undo("foo", Duration::seconds(10)).err(),
+        Some(GameError::NothingToUndo)
+    );
+
+    let game = game
+        .start_turn("foo".to_string(), ("bar".to_string(), 1))
+        .unwrap();
+    assert_eq!(
+        game.undo("foo", Duration::zero()).err(),
+        Some(GameError::UndoExpired)
+    );
+}
+
+#[test]
+fn reroll() {
+    let game = test_game_with(GameSettings {
+        reroll_limit: 2,
+        ..GameSettings::default()
+    })
+    .reroll("bar", 17, "ghost".to_string())
+    .unwrap();
+
+    assert_eq!(game.board[17].word, "ghost");
+    assert_eq!(game.board[17].color, CardColor::Death);
+    assert!(game.used_words.contains("ghost"));
+    assert_eq!(
+        GameData::from_history(&game.history).unwrap().unwrap(),
+        game
+    );
+    assert!(matches!(
+        game.clone().reroll("bar", 0, "ghost".to_string()).err(),
+        Some(GameError::InvalidWordList(_))
+    ));
+
+    let game = game.reroll("bar", 0, "witch".to_string()).unwrap();
+    assert_eq!(
+        game.clone().reroll("bar", 1, "moon".to_string()).err(),
+        Some(GameError::RerollLimit(2))
+    );
+    assert_eq!(
+        test_game()
+            .start_turn("foo".to_string(), ("bar".to_string(), 1))
+            .unwrap()
+            .reroll("bar", 1, "moon".to_string())
+            .err(),
+        Some(GameError::RerollClosed)
+    );
+}
+
 const GAME_JSON: &str = r#"
 {
   "name": "test",
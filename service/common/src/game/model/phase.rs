@@ -0,0 +1,26 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Lowercase;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Phase {
+    /// Players are joining and picking teams and roles. Holds the players who have marked ready.
+    Lobby(HashSet<Lowercase>),
+    Started,
+}
+
+impl Phase {
+    pub fn lobby() -> Self {
+        Phase::Lobby(HashSet::new())
+    }
+}
+
+/// Games saved before the lobby phase existed are already in play.
+impl Default for Phase {
+    fn default() -> Self {
+        Phase::Started
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Options chosen when a game is created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSettings {
+    /// Seconds a spymaster has to give a clue, if limited, up to an hour. The first clue of a game
+    /// without a lobby is never timed, since the players may still be joining.
+    #[serde(default)]
+    pub clue_time_limit: Option<u64>,
+    /// Seconds the operatives have to guess once the clue is given, if limited, up to an hour.
+    #[serde(default)]
+    pub guess_time_limit: Option<u64>,
+    /// Dictionaries to draw the words from, by name, with the share of the board each one gets.
+    /// Empty means the default dictionary.
+    #[serde(default)]
+    pub dictionaries: BTreeMap<String, u32>,
+    /// Which words the board favours, going by their difficulty in the dictionary. Anything but
+    /// mixed needs dictionaries that tag their words with a difficulty.
+    #[serde(default)]
+    pub difficulty: BoardDifficulty,
+    /// How many cards may have their word replaced before the first clue. Always zero for games
+    /// with custom words and for daily challenges.
+    #[serde(default = "default_reroll_limit")]
+    pub reroll_limit: u32,
+    /// Whether players gather in a lobby and mark ready before the host starts the game. Without
+    /// it the game is in play as soon as it is created.
+    #[serde(default)]
+    pub lobby: bool,
+}
+
+pub const DEFAULT_REROLL_LIMIT: u32 = 3;
+
+fn default_reroll_limit() -> u32 {
+    DEFAULT_REROLL_LIMIT
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            clue_time_limit: None,
+            guess_time_limit: None,
+            dictionaries: BTreeMap::new(),
+            difficulty: BoardDifficulty::default(),
+            reroll_limit: DEFAULT_REROLL_LIMIT,
+            lobby: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardDifficulty {
+    Easy,
+    #[default]
+    Mixed,
+    Hard,
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::game::model::{Player, Team};
@@ -6,7 +7,10 @@ use crate::game::model::{Player, Team};
 pub struct TurnData {
     pub spymaster: Player,
     pub clue: (String, usize),
-    pub guesses: Vec<(Player, usize)>,
+    /// Newest first.
+    pub guesses: Vec<Guess>,
+    #[serde(default)]
+    pub clue_given_at: Option<DateTime<Utc>>,
 }
 
 impl TurnData {
@@ -15,10 +19,47 @@ impl TurnData {
             spymaster,
             clue,
             guesses: Vec::new(),
+            clue_given_at: None,
         }
     }
 }
 
+/// A card revealed by an operative. Serialized as `[player, index, at]`, like the pairs guesses
+/// were stored as before they were timed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "GuessBody", from = "GuessBody")]
+pub struct Guess {
+    pub player: Player,
+    pub index: usize,
+    /// `None` for guesses made before they were timed.
+    pub at: Option<DateTime<Utc>>,
+}
+
+impl Guess {
+    pub fn new(player: Player, index: usize) -> Self {
+        Self {
+            player,
+            index,
+            at: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GuessBody(Player, usize, #[serde(default)] Option<DateTime<Utc>>);
+
+impl From<Guess> for GuessBody {
+    fn from(guess: Guess) -> Self {
+        GuessBody(guess.player, guess.index, guess.at)
+    }
+}
+
+impl From<GuessBody> for Guess {
+    fn from(GuessBody(player, index, at): GuessBody) -> Self {
+        Guess { player, index, at }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum Turn {
@@ -26,6 +67,40 @@ pub enum Turn {
     Started(TurnData),
 }
 
+/// When the current phase of a turn runs out. Serialized with the time left, as of when it is
+/// sent, so clients do not depend on their own clocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "DeadlineBody", from = "DeadlineBody")]
+pub struct Deadline(pub DateTime<Utc>);
+
+impl Deadline {
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        (self.0 - now).max(Duration::zero())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeadlineBody {
+    at: DateTime<Utc>,
+    #[serde(default)]
+    seconds_remaining: i64,
+}
+
+impl From<Deadline> for DeadlineBody {
+    fn from(deadline: Deadline) -> Self {
+        DeadlineBody {
+            at: deadline.0,
+            seconds_remaining: deadline.remaining(Utc::now()).num_seconds(),
+        }
+    }
+}
+
+impl From<DeadlineBody> for Deadline {
+    fn from(body: DeadlineBody) -> Self {
+        Deadline(body.at)
+    }
+}
+
 impl Turn {
     pub fn team(&self) -> &Team {
         match self {
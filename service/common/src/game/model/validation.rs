@@ -0,0 +1,241 @@
+use std::{collections::HashSet, convert::TryInto};
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    game::model::{GameError, GameSettings, BOARD_SIZE},
+    Lowercase,
+};
+
+pub const MAX_PLAYER_NAME_LENGTH: usize = 32;
+pub const MAX_GAME_NAME_LENGTH: usize = 64;
+pub const MAX_WORD_POOL_NAME_LENGTH: usize = 64;
+pub const MAX_WORD_LENGTH: usize = 24;
+/// A pool needs more words than a board, so that boards drawn from it differ.
+pub const MIN_WORD_POOL_SIZE: usize = BOARD_SIZE + 1;
+pub const MAX_WORD_POOL_SIZE: usize = 1000;
+pub const MAX_TIME_LIMIT_SECONDS: u64 = 60 * 60;
+
+/// Characters which would break the URL paths that player and game names are used in.
+const RESERVED_NAME_CHARACTERS: [char; 5] = ['/', '\\', '?', '#', '%'];
+
+pub fn validate_board_index(board_index: usize) -> Result<usize, GameError> {
+    if board_index >= BOARD_SIZE {
+        return Err(GameError::InvalidGuess(format!(
+            "board index {} is out of range, it must be less than {}",
+            board_index, BOARD_SIZE
+        )));
+    }
+    Ok(board_index)
+}
+
+pub fn validate_player_name(name: &str) -> Result<String, GameError> {
+    validate_name("player", name, MAX_PLAYER_NAME_LENGTH)
+}
+
+pub fn validate_game_name(name: &str) -> Result<String, GameError> {
+    validate_name("game", name, MAX_GAME_NAME_LENGTH)
+}
+
+pub fn validate_word_pool_name(name: &str) -> Result<String, GameError> {
+    validate_name("word pool", name, MAX_WORD_POOL_NAME_LENGTH)
+}
+
+/// Checks the options a game is created with.
+pub fn validate_settings(settings: &GameSettings) -> Result<(), GameError> {
+    for (name, limit) in [
+        ("clue_time_limit", settings.clue_time_limit),
+        ("guess_time_limit", settings.guess_time_limit),
+    ] {
+        match limit {
+            Some(seconds) if seconds == 0 || seconds > MAX_TIME_LIMIT_SECONDS => {
+                return Err(GameError::InvalidSettings(format!(
+                    "{} must be between 1 and {} seconds, got {}",
+                    name, MAX_TIME_LIMIT_SECONDS, seconds
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks a custom list of exactly one word per card.
+pub fn validate_board_words(words: &[String]) -> Result<[String; BOARD_SIZE], GameError> {
+    let words = validate_words(words)?;
+    let count = words.len();
+    words.try_into().map_err(|_| {
+        GameError::InvalidWordList(format!(
+            "a board needs {} distinct words, got {}",
+            BOARD_SIZE, count
+        ))
+    })
+}
+
+/// Checks a custom pool of words for boards to be drawn from.
+pub fn validate_word_pool(words: &[String]) -> Result<HashSet<String>, GameError> {
+    let words = validate_words(words)?;
+    if words.len() < MIN_WORD_POOL_SIZE || words.len() > MAX_WORD_POOL_SIZE {
+        return Err(GameError::InvalidWordList(format!(
+            "a word pool needs between {} and {} distinct words, got {}",
+            MIN_WORD_POOL_SIZE,
+            MAX_WORD_POOL_SIZE,
+            words.len()
+        )));
+    }
+    Ok(words.into_iter().collect())
+}
+
+/// Normalizes each word and drops repeats, ignoring case. Keeps the first spelling.
+fn validate_words(words: &[String]) -> Result<Vec<String>, GameError> {
+    let mut seen = HashSet::new();
+    let mut validated = Vec::new();
+    for word in words {
+        let word = validate_word(word)?;
+        if seen.insert(Lowercase::new(word.as_str())) {
+            validated.push(word);
+        }
+    }
+    Ok(validated)
+}
+
+/// Normalizes a word like a name and checks its length and characters.
+pub fn validate_word(word: &str) -> Result<String, GameError> {
+    let word = normalize(word);
+    let length = word.chars().count();
+    if length == 0 || length > MAX_WORD_LENGTH {
+        return Err(GameError::InvalidWordList(format!(
+            "words must be between 1 and {} characters: {:?}",
+            MAX_WORD_LENGTH, word
+        )));
+    }
+    if word.chars().any(|c| c.is_control()) {
+        return Err(GameError::InvalidWordList(format!(
+            "word contains a control character: {:?}",
+            word
+        )));
+    }
+    Ok(word)
+}
+
+fn normalize(text: &str) -> String {
+    text.nfkc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// NFKC-normalizes the name and collapses runs of whitespace into a single space,
+/// then checks it against the length and character rules.
+/// Returns the normalized name, which is what should be stored.
+fn validate_name(kind: &str, name: &str, max_length: usize) -> Result<String, GameError> {
+    let normalized = normalize(name);
+
+    let length = normalized.chars().count();
+    if length == 0 {
+        return Err(GameError::InvalidName(format!(
+            "{} name must not be empty",
+            kind
+        )));
+    }
+    if length > max_length {
+        return Err(GameError::InvalidName(format!(
+            "{} name must be at most {} characters: {}",
+            kind, max_length, normalized
+        )));
+    }
+    if let Some(c) = normalized
+        .chars()
+        .find(|c| c.is_control() || RESERVED_NAME_CHARACTERS.contains(c))
+    {
+        return Err(GameError::InvalidName(format!(
+            "{} name contains a disallowed character: {:?}",
+            kind, c
+        )));
+    }
+
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::model::{
+            validate_board_index, validate_board_words, validate_game_name, validate_player_name,
+            validate_settings, validate_word_pool, GameError, GameSettings,
+        },
+        Lowercase,
+    };
+
+    #[test]
+    fn board_index_out_of_range() {
+        assert_eq!(validate_board_index(24), Ok(24));
+        assert!(matches!(
+            validate_board_index(25),
+            Err(GameError::InvalidGuess(_))
+        ));
+    }
+
+    #[test]
+    fn name_is_normalized() {
+        assert_eq!(
+            validate_player_name("  mr \t spy  master ").unwrap(),
+            "mr spy master"
+        );
+        assert_eq!(validate_player_name("ｆｏｏ").unwrap(), "foo");
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert!(validate_player_name("   ").is_err());
+        assert!(validate_player_name("foo\u{0}bar").is_err());
+        assert!(validate_player_name("foo/bar").is_err());
+        assert!(validate_player_name(&"a".repeat(33)).is_err());
+        assert!(validate_game_name(&"a".repeat(64)).is_ok());
+    }
+
+    #[test]
+    fn lowercase_is_normalized() {
+        assert_eq!(Lowercase::new("Cafe\u{301}"), Lowercase::new("CAFÉ"));
+    }
+
+    #[test]
+    fn board_words() {
+        let words: Vec<String> = (0..25).map(|i| format!(" word  {} ", i)).collect();
+        let board = validate_board_words(&words).unwrap();
+        assert_eq!(board[3], "word 3");
+
+        let repeated = [words[..24].to_vec(), vec!["WORD 0".to_string()]].concat();
+        assert!(matches!(
+            validate_board_words(&repeated),
+            Err(GameError::InvalidWordList(_))
+        ));
+    }
+
+    #[test]
+    fn word_pool() {
+        let words: Vec<String> = (0..26).map(|i| i.to_string()).collect();
+        assert_eq!(validate_word_pool(&words).unwrap().len(), 26);
+        assert!(validate_word_pool(&words[1..]).is_err());
+        assert!(validate_word_pool(&[words.clone(), vec!["x".repeat(25)]].concat()).is_err());
+    }
+
+    #[test]
+    fn time_limits() {
+        let limits = |clue, guess| GameSettings {
+            clue_time_limit: clue,
+            guess_time_limit: guess,
+            ..GameSettings::default()
+        };
+        assert!(validate_settings(&limits(None, None)).is_ok());
+        assert!(validate_settings(&limits(Some(1), Some(3600))).is_ok());
+        for invalid in [0, 3601, 1_000_000_000_000_000, u64::MAX] {
+            assert!(matches!(
+                validate_settings(&limits(Some(invalid), None)),
+                Err(GameError::InvalidSettings(_))
+            ));
+            assert!(validate_settings(&limits(None, Some(invalid))).is_err());
+        }
+    }
+}
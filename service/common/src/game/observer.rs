@@ -0,0 +1,10 @@
+use dyn_clone::DynClone;
+
+use crate::game::model::GameData;
+
+/// Notified by `GameService` each time a game is saved, e.g. to push updates to connected clients.
+pub trait GameObserver: DynClone + Send + Sync {
+    fn game_saved(&self, game: &GameData);
+}
+
+dyn_clone::clone_trait_object!(GameObserver);
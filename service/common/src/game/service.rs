@@ -1,20 +1,56 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDate, Utc};
 use log::{debug, info, warn};
+use rand::{thread_rng, Rng};
+use serde_json::json;
 
 use crate::{
-    dictionary::{DictionaryService, WordGenerator},
+    dictionary::{
+        dictionary_loader_from_env, DictionaryLoader, DictionaryReport, DictionaryService,
+        DictionarySummary, WordGenerator, WordGeneratorRand,
+    },
     game::{
+        board::board_generator_from_env,
         board_service::{BoardGenerator, BoardService},
+        daily::{daily_seed, leaderboard, DailyResult},
         dao::GameDao,
-        model::{Game, GameData, GameState, Player},
+        model::{
+            validate_board_words, validate_game_name, validate_settings, validate_word_pool,
+            validate_word_pool_name, Game, GameData, GameError, GameSettings, GameState, Player,
+            Team,
+        },
+        observer::GameObserver,
     },
-    DaoError, Lowercase, ServiceError, ServiceResult, StdResult,
+    CustomWords, DaoError, ErrorBody, Lowercase, ServiceError, ServiceResult, StdResult,
 };
 
+/// How often `get_since` re-reads a game while waiting for it to change.
+const LONG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Seeds picked for new games stay below 2^53, so JavaScript clients can hold them exactly.
+const MAX_GENERATED_SEED: u64 = 1 << 53;
+
+/// How long after a clue or guess it can still be undone, unless configured otherwise.
+const DEFAULT_UNDO_WINDOW: Duration = Duration::from_secs(15);
+
 #[derive(Clone)]
 pub struct GameService {
     board_service: BoardService,
     dictionary_service: DictionaryService,
     dao: Box<dyn GameDao>,
+    observers: Vec<Box<dyn GameObserver>>,
+    undo_window: Duration,
+    /// Secret the board of the day is derived from. Without it there is no daily challenge.
+    daily_key: Option<String>,
+    /// Secret for administrative operations. Without it they are unavailable.
+    admin_secret: Option<String>,
+    /// The version a game must still have when it is saved, for a request made with `If-Match`.
+    expected_version: Option<u64>,
 }
 
 impl GameService {
@@ -30,29 +66,271 @@ impl GameService {
             board_service,
             dictionary_service,
             dao,
+            observers: Vec::new(),
+            undo_window: DEFAULT_UNDO_WINDOW,
+            daily_key: None,
+            admin_secret: None,
+            expected_version: None,
+        })
+    }
+
+    /// Builds the service a deployment runs, configured from the environment: the board and
+    /// dictionary sources (see `board_generator_from_env` and `dictionary_loader_from_env`),
+    /// `CODENAMES_DAILY_KEY`, `CODENAMES_ADMIN_SECRET` and `CODENAMES_UNDO_WINDOW_SECS`.
+    pub async fn from_env(dao: Box<dyn GameDao>) -> StdResult<GameService> {
+        let service = GameService::new(
+            Box::new(WordGeneratorRand),
+            board_generator_from_env()?,
+            dao.clone(),
+        )?
+        .with_dictionary_loader(dictionary_loader_from_env(dao))
+        .await;
+        let service = match env::var("CODENAMES_DAILY_KEY") {
+            Ok(key) => service.with_daily_key(key),
+            Err(_) => service,
+        };
+        let service = match env::var("CODENAMES_ADMIN_SECRET") {
+            Ok(secret) => service.with_admin_secret(secret),
+            Err(_) => service,
+        };
+        Ok(match env::var("CODENAMES_UNDO_WINDOW_SECS") {
+            Ok(seconds) => service.with_undo_window(Duration::from_secs(seconds.parse()?)),
+            Err(_) => service,
         })
     }
 
+    pub fn with_observer(self, observer: Box<dyn GameObserver>) -> GameService {
+        GameService {
+            observers: [self.observers, vec![observer]].concat(),
+            ..self
+        }
+    }
+
+    pub fn with_undo_window(self, undo_window: Duration) -> GameService {
+        GameService {
+            undo_window,
+            ..self
+        }
+    }
+
+    pub fn with_daily_key(self, daily_key: String) -> GameService {
+        GameService {
+            daily_key: Some(daily_key),
+            ..self
+        }
+    }
+
+    pub fn with_admin_secret(self, admin_secret: String) -> GameService {
+        GameService {
+            admin_secret: Some(admin_secret),
+            ..self
+        }
+    }
+
+    /// Makes saves fail with `STALE_GAME` unless the game is still at `version`, for a client that
+    /// only wants to change the game it has seen.
+    pub fn with_expected_version(self, version: u64) -> GameService {
+        GameService {
+            expected_version: Some(version),
+            ..self
+        }
+    }
+
+    pub async fn with_dictionary_loader(self, loader: Box<dyn DictionaryLoader>) -> GameService {
+        GameService {
+            dictionary_service: self.dictionary_service.clone().with_loader(loader).await,
+            ..self
+        }
+    }
+
+    pub fn dictionaries(&self) -> Vec<DictionarySummary> {
+        self.dictionary_service.dictionaries()
+    }
+
+    /// Loads the dictionaries again from their source, for a caller with the admin secret.
+    pub async fn reload_dictionaries(
+        &self,
+        admin_secret: Option<&str>,
+    ) -> ServiceResult<Vec<DictionaryReport>> {
+        self.check_admin(admin_secret)?;
+        self.dictionary_service.reload().await
+    }
+
+    fn check_admin(&self, provided: Option<&str>) -> ServiceResult<()> {
+        let secret = self.admin_secret.as_deref().ok_or_else(|| {
+            ServiceError::NotFound(ErrorBody::new(
+                "NOT_FOUND",
+                "admin operations are not configured".into(),
+            ))
+        })?;
+        // Compares every byte, so the time taken does not reveal how much of the secret matched.
+        let matches = provided
+            .filter(|provided| provided.len() == secret.len())
+            .map(|provided| {
+                provided
+                    .bytes()
+                    .zip(secret.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+            })
+            .unwrap_or(false);
+        if matches {
+            Ok(())
+        } else {
+            Err(ServiceError::Forbidden(ErrorBody::new(
+                "NOT_AN_ADMIN",
+                "the admin secret is missing or wrong".into(),
+            )))
+        }
+    }
+
     pub async fn random_name(&self) -> ServiceResult<String> {
         debug!("call: game.Service.random_name");
         let (first_name, last_name) = self.dictionary_service.new_word_pair().await?;
         Ok(format!("{}-{}", first_name, last_name))
     }
 
-    pub async fn new_game(&self, game_name: String) -> ServiceResult<GameState> {
-        let words = self.dictionary_service.new_word_set().await?;
-        let (board, first_team) = self.board_service.new_board(words).await?;
+    pub async fn new_game(
+        &self,
+        game_name: String,
+        settings: GameSettings,
+        custom_words: Option<CustomWords>,
+        seed: Option<u64>,
+    ) -> ServiceResult<GameState> {
+        self.create_game(game_name, settings, custom_words, seed, None)
+            .await
+    }
+
+    /// Starts a game on today's board, which is the same for every game started the same day.
+    pub async fn new_daily_game(&self, game_name: String) -> ServiceResult<GameState> {
+        let date = Utc::today().naive_utc();
+        let seed = daily_seed(self.daily_key()?, date);
+        // Every team plays the same board, so none of them may swap its words.
+        let settings = GameSettings {
+            reroll_limit: 0,
+            ..GameSettings::default()
+        };
+        self.create_game(game_name, settings, None, Some(seed), Some(date))
+            .await
+    }
+
+    /// The results of the daily challenge of `date`, best first.
+    pub async fn daily_leaderboard(&self, date: NaiveDate) -> ServiceResult<Vec<DailyResult>> {
+        self.daily_key()?;
+        let results = self.clone().dao.daily_results(date).await?;
+        Ok(leaderboard(results))
+    }
+
+    fn daily_key(&self) -> ServiceResult<&str> {
+        self.daily_key.as_deref().ok_or_else(|| {
+            ServiceError::NotFound(ErrorBody::new(
+                "NOT_FOUND",
+                "daily challenge is not configured".into(),
+            ))
+        })
+    }
+
+    async fn create_game(
+        &self,
+        game_name: String,
+        settings: GameSettings,
+        custom_words: Option<CustomWords>,
+        seed: Option<u64>,
+        daily: Option<NaiveDate>,
+    ) -> ServiceResult<GameState> {
+        let game_name = validate_game_name(&game_name)?;
+        validate_settings(&settings)?;
+        // Custom words have no dictionary to draw a replacement from, so their cards stay put.
+        let settings = match custom_words {
+            Some(_) => GameSettings {
+                reroll_limit: 0,
+                ..settings
+            },
+            None => settings,
+        };
+        let previous = self.used_words(&game_name).await?;
+        // A shared seed gives the same board whatever was played before, so nothing is left out.
+        let shared = seed.is_some();
+        let (seed, used) = match seed {
+            Some(seed) => (seed, BTreeSet::new()),
+            None => (
+                thread_rng().gen_range(0..MAX_GENERATED_SEED),
+                previous.clone(),
+            ),
+        };
+        let (words, used) = match custom_words {
+            None => {
+                self.dictionary_service
+                    .new_word_set(&settings.dictionaries, settings.difficulty, used, seed)
+                    .await?
+            }
+            Some(CustomWords::Board { words }) => {
+                let words = validate_board_words(&words)?;
+                let used = used.into_iter().chain(words.iter().cloned()).collect();
+                (words, used)
+            }
+            Some(CustomWords::Pool { words, save_as }) => {
+                let pool = validate_word_pool(&words)?;
+                if let Some(name) = save_as {
+                    let name = validate_word_pool_name(&name)?;
+                    let mut sorted: Vec<String> = pool.iter().cloned().collect();
+                    sorted.sort();
+                    self.clone()
+                        .dao
+                        .set_word_pool(Lowercase::new(name.as_str()), sorted)
+                        .await?;
+                }
+                self.dictionary_service
+                    .new_word_set_from(pool, used, seed)
+                    .await?
+            }
+            Some(CustomWords::Saved { name }) => {
+                let pool = self
+                    .clone()
+                    .dao
+                    .get_word_pool(Lowercase::new(&name))
+                    .await?;
+                self.dictionary_service
+                    .new_word_set_from(pool.into_iter().collect(), used, seed)
+                    .await?
+            }
+        };
+        let (board, first_team, key_card) = self.board_service.new_board(words, seed).await?;
+        let used = if shared {
+            previous.into_iter().chain(used).collect()
+        } else {
+            used
+        };
 
-        let game = GameData::new(game_name, board, first_team);
-        let _ = &self.clone().save(game.clone()).await?;
+        let game = GameData::new(game_name, board, first_team, settings)
+            .with_used_words(used)
+            .with_seed(seed)
+            .with_key_card(key_card);
+        let game = match daily {
+            Some(date) => GameData {
+                info: game.info.with_daily(date),
+                ..game
+            },
+            None => game,
+        };
+        let game = self.clone().save(game).await?;
 
         Ok(game.clone().into())
     }
 
+    /// The words the game being replaced by a new one of the same name had used.
+    async fn used_words(&self, game_name: &str) -> ServiceResult<BTreeSet<String>> {
+        match self.clone().dao.get(Lowercase::new(game_name)).await {
+            Ok(previous) => Ok(previous.used_words),
+            Err(DaoError::NotFound(_)) => Ok(BTreeSet::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub async fn join(&self, key: String, player: Player) -> ServiceResult<Game> {
         let game = &self.clone()._get(&key).await?;
         let updated_game = game.clone().join(player.clone())?;
-        let _ = &self.clone().save(updated_game.clone()).await?;
+        let updated_game = self.clone().save(updated_game).await?;
         Ok((player.clone(), updated_game).into())
     }
 
@@ -61,14 +339,114 @@ impl GameService {
         println!("got game {}", game.info.name());
         let updated_game = game.clone().leave(player_name)?;
         println!("left game {}", game.info.name());
-        let _ = &self.clone().save(updated_game.clone()).await?;
+        let updated_game = self.clone().save(updated_game).await?;
+        Ok(updated_game.clone().into())
+    }
+
+    pub async fn set_ready(
+        &self,
+        key: String,
+        player_name: &str,
+        ready: bool,
+    ) -> ServiceResult<Game> {
+        let game = &self.clone()._get(&key).await?;
+        let updated_game = game.clone().set_ready(player_name, ready)?;
+        let updated_game = self.clone().save(updated_game).await?;
+        self.player_view(player_name, updated_game)
+    }
+
+    pub async fn start_game(&self, key: String, player_name: &str) -> ServiceResult<Game> {
+        let game = &self.clone()._get(&key).await?;
+        let updated_game = game.clone().start_game(player_name)?;
+        let updated_game = self.clone().save(updated_game).await?;
+        self.player_view(player_name, updated_game)
+    }
+
+    pub async fn switch_player(
+        &self,
+        key: String,
+        player_name: &str,
+        team: Team,
+        spymaster_secret: Option<String>,
+    ) -> ServiceResult<Game> {
+        let game = &self.clone()._get(&key).await?;
+        let updated_game = game
+            .clone()
+            .switch_player(player_name, team, spymaster_secret)?;
+        let updated_game = self.clone().save(updated_game).await?;
+        self.player_view(player_name, updated_game)
+    }
+
+    fn player_view(&self, player_name: &str, game: GameData) -> ServiceResult<Game> {
+        let player = game
+            .info
+            .player(player_name)
+            .cloned()
+            .ok_or_else(|| GameError::PlayerNotFound(player_name.to_string()))?;
+        Ok((player, game).into())
+    }
+
+    pub async fn hand_off_spymaster(
+        &self,
+        key: String,
+        spymaster_name: &str,
+        teammate_name: &str,
+        spymaster_secret: String,
+    ) -> ServiceResult<GameState> {
+        let game = &self.clone()._get(&key).await?;
+        let updated_game =
+            game.clone()
+                .hand_off_spymaster(spymaster_name, teammate_name, spymaster_secret)?;
+        let updated_game = self.clone().save(updated_game).await?;
         Ok(updated_game.clone().into())
     }
 
     pub async fn guess(&self, key: String, guess: (&str, usize)) -> ServiceResult<GameState> {
         let game = &self.clone()._get(&key).await?;
         let updated_game = game.clone().guess(guess)?;
-        let _ = &self.clone().save(updated_game.clone()).await?;
+        let updated_game = self.clone().save(updated_game).await?;
+        if let (Some(date), false, true) = (
+            updated_game.info.daily(),
+            game.is_over(),
+            updated_game.is_over(),
+        ) {
+            for result in DailyResult::from_game(&updated_game, Utc::now()) {
+                self.clone().dao.add_daily_result(*date, result).await?;
+            }
+        }
+        Ok(updated_game.clone().into())
+    }
+
+    /// Replaces the word on a card with another from the dictionary it came from.
+    pub async fn reroll(
+        &self,
+        key: String,
+        player_name: &str,
+        board_index: usize,
+    ) -> ServiceResult<Game> {
+        let game = &self.clone()._get(&key).await?;
+        game.check_reroll(player_name, board_index)?;
+        let taken: Vec<String> = game.board.iter().map(|card| card.word.clone()).collect();
+        let word = self
+            .dictionary_service
+            .new_word_like(
+                &game.info.settings().dictionaries,
+                &taken[board_index],
+                &taken,
+                &game.used_words,
+            )
+            .await?;
+        let updated_game = game.clone().reroll(player_name, board_index, word)?;
+        let updated_game = self.clone().save(updated_game).await?;
+        self.player_view(player_name, updated_game)
+    }
+
+    pub async fn undo(&self, key: String, player_name: &str) -> ServiceResult<GameState> {
+        let game = &self.clone()._get(&key).await?;
+        let window = chrono::Duration::from_std(self.undo_window)
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+        let updated_game = game.clone().undo(player_name, window)?;
+        let updated_game = self.clone().save(updated_game).await?;
         Ok(updated_game.clone().into())
     }
 
@@ -80,22 +458,29 @@ impl GameService {
     ) -> ServiceResult<GameData> {
         let game = &self.clone()._get(&key).await?;
         let updated_game = game.clone().start_turn(spymaster_name, clue)?;
-        let _ = &self.clone().save(updated_game.clone()).await?;
+        let updated_game = self.clone().save(updated_game).await?;
         Ok(updated_game.clone())
     }
 
     pub async fn end_turn(&self, key: String) -> ServiceResult<GameState> {
         let game = &self.clone()._get(&key).await?;
         let updated_game = game.clone().end_turn();
-        let _ = &self.clone().save(updated_game.clone()).await?;
+        let updated_game = self.clone().save(updated_game).await?;
         Ok(updated_game.clone().into())
     }
 
+    /// Loads a game, first ending the current turn if it has run out of time.
     async fn _get(&mut self, key: &str) -> ServiceResult<GameData> {
-        self.dao.get(Lowercase::new(key)).await.map_err(|e| {
+        let game = self.dao.get(Lowercase::new(key)).await.map_err(|e| {
             info!("{}", e);
-            e.into()
-        })
+            ServiceError::from(e)
+        })?;
+        let now = Utc::now();
+        if game.turn_expired(now) {
+            self.save(game.expire_turn(now)).await
+        } else {
+            Ok(game)
+        }
     }
 
     pub async fn get(
@@ -105,15 +490,87 @@ impl GameService {
         spymaster_secret: &Option<String>,
     ) -> ServiceResult<Game> {
         let data = self._get(key).await?;
+        GameService::view(data, player_name, spymaster_secret)
+    }
+
+    /// Like `get`, but only returns the game once its version is newer than `since`, waiting up to
+    /// `wait` for that to happen. `None` means it did not change in time.
+    pub async fn get_since(
+        &mut self,
+        key: &str,
+        player_name: &Option<String>,
+        spymaster_secret: &Option<String>,
+        since: u64,
+        wait: Duration,
+    ) -> ServiceResult<Option<Game>> {
+        let deadline = Instant::now() + wait;
+        loop {
+            let data = self._get(key).await?;
+            if data.info.version() > since {
+                return GameService::view(data, player_name, spymaster_secret).map(Some);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(LONG_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// The game as it was after its first `step` events (creating it is step 0), seen by the viewer.
+    /// A player who had not joined yet by then sees the public projection. Once the game is over
+    /// every card is revealed to everyone.
+    pub async fn replay(
+        &mut self,
+        key: &str,
+        step: usize,
+        player_name: &Option<String>,
+        spymaster_secret: &Option<String>,
+    ) -> ServiceResult<Game> {
+        let data = self._get(key).await?;
+        let history = data.history.get(..=step).ok_or_else(|| {
+            ServiceError::NotFound(ErrorBody::new("NOT_FOUND", format!("step: {}", step)))
+        })?;
+        let replayed = GameData::from_history(history).ok_or_else(|| {
+            ServiceError::NotFound(ErrorBody::new("NOT_FOUND", format!("history: {}", key)))
+        })??;
+        match player_name {
+            _ if data.is_over() => Ok(Game::Data(replayed)),
+            Some(name) if replayed.info.player(name).is_none() => {
+                data.info
+                    .player(name)
+                    .ok_or_else(|| GameError::PlayerNotFound(name.to_string()))?;
+                Ok(Game::State(replayed.into()))
+            }
+            _ => GameService::view(replayed, player_name, spymaster_secret),
+        }
+    }
+
+    /// The error for a change to a game that has been saved again since the client fetched it.
+    pub fn stale_game(version: u64) -> ServiceError {
+        ServiceError::PreconditionFailed(
+            ErrorBody::new(
+                "STALE_GAME",
+                "the game has changed since it was fetched".into(),
+            )
+            .with_details(json!({ "version": version })),
+        )
+    }
+
+    /// Projects a game for a viewer: `GameData` for a spymaster who provides the correct secret,
+    /// `GameState` for everyone else.
+    pub fn view(
+        data: GameData,
+        player_name: &Option<String>,
+        spymaster_secret: &Option<String>,
+    ) -> ServiceResult<Game> {
         match player_name {
             None => Ok(Game::State(data.into())),
             Some(player_name) => {
-                let &player = data
+                let player = data
                     .info
-                    .players()
-                    .iter()
-                    .find(|&p| p.name.to_lowercase() == player_name.to_lowercase())
-                    .ok_or(ServiceError::NotFound(format!("player: {}", player_name)))?;
+                    .player(player_name)
+                    .ok_or_else(|| GameError::PlayerNotFound(player_name.to_string()))?;
                 match (&player.spymaster_secret, spymaster_secret) {
                     (None, _) => Ok((player.clone(), data).into()),
                     (Some(player_secret), Some(provided_secret))
@@ -135,18 +592,41 @@ impl GameService {
             .map(|ls| ls.iter().map(|l| l.value().to_string()).collect())
             .map_err(|e| {
                 warn!("{}", e);
-                let de: DaoError = e.into();
-                de
+                e
             })?;
 
         Ok(games)
     }
 
-    async fn save(&mut self, game: GameData) -> ServiceResult<()> {
+    /// Stores the game with its version incremented, and returns what was stored.
+    async fn save(&mut self, game: GameData) -> ServiceResult<GameData> {
         let key = Lowercase::new(game.info.name());
-        self.dao.set(key, game).await.map_err(|e| {
-            warn!("{}", e);
-            e.into()
-        })
+        match self.expected_version {
+            Some(version) if version != game.info.version() => {
+                return Err(GameService::stale_game(game.info.version()))
+            }
+            _ => {}
+        }
+        let game = GameData {
+            info: game.info.next_version(),
+            ..game
+        };
+        let saved = self
+            .dao
+            .set(key.clone(), game.clone(), self.expected_version)
+            .await;
+        match saved {
+            Ok(()) => {}
+            Err(DaoError::Conflict(_)) => {
+                let current = self.dao.get(key).await?;
+                return Err(GameService::stale_game(current.info.version()));
+            }
+            Err(e) => {
+                warn!("{}", e);
+                return Err(e.into());
+            }
+        }
+        self.observers.iter().for_each(|o| o.game_saved(&game));
+        Ok(game)
     }
 }
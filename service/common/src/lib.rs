@@ -4,7 +4,17 @@ extern crate serde_json;
 
 use std::{error::Error, fmt, fmt::Formatter};
 
+use chrono::NaiveDate;
 use serde::{de, de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    dictionary::{DictionaryReport, DictionarySummary},
+    game::{
+        daily::DailyResult,
+        model::{GameSettings, Team},
+    },
+};
 
 pub mod dictionary;
 pub mod game;
@@ -15,25 +25,51 @@ pub type StdResult<T> = std::result::Result<T, StdError>;
 #[derive(Debug, Serialize, Clone)]
 pub enum ServiceError {
     BadRequest(String),
-    NotFound(String),
+    Forbidden(ErrorBody),
+    NotFound(ErrorBody),
+    Conflict(ErrorBody),
+    Unprocessable(ErrorBody),
+    PreconditionFailed(ErrorBody),
     Unknown(String),
 }
 
+impl ServiceError {
+    /// The machine-readable representation of this error, suitable for a response body.
+    pub fn body(&self) -> ErrorBody {
+        match self {
+            ServiceError::BadRequest(msg) => ErrorBody::new("BAD_REQUEST", msg.clone()),
+            ServiceError::Unknown(msg) => ErrorBody::new("UNKNOWN", msg.clone()),
+            ServiceError::Forbidden(body)
+            | ServiceError::NotFound(body)
+            | ServiceError::Conflict(body)
+            | ServiceError::Unprocessable(body)
+            | ServiceError::PreconditionFailed(body) => body.clone(),
+        }
+    }
+}
+
 impl fmt::Display for ServiceError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match &self {
             ServiceError::BadRequest(msg) => format!("Bad Request: {}", msg),
-            ServiceError::NotFound(msg) => format!("Not Found: {}", msg),
+            ServiceError::Forbidden(body) => format!("Forbidden: {}", body.message),
+            ServiceError::NotFound(body) => format!("Not Found: {}", body.message),
+            ServiceError::Conflict(body) => format!("Conflict: {}", body.message),
+            ServiceError::Unprocessable(body) => format!("Unprocessable: {}", body.message),
+            ServiceError::PreconditionFailed(body) => {
+                format!("Precondition Failed: {}", body.message)
+            }
             ServiceError::Unknown(msg) => format!("Unknown: {}", msg),
         };
-        write!(f, "{}", format!("Service Error: {}", msg))
+        write!(f, "Service Error: {}", msg)
     }
 }
 
 impl From<DaoError> for ServiceError {
     fn from(dao_error: DaoError) -> Self {
         match dao_error {
-            DaoError::NotFound(msg) => ServiceError::NotFound(msg),
+            DaoError::NotFound(msg) => ServiceError::NotFound(ErrorBody::new("NOT_FOUND", msg)),
+            DaoError::Conflict(msg) => ServiceError::Conflict(ErrorBody::new("CONFLICT", msg)),
             DaoError::Unknown(msg) => ServiceError::Unknown(msg),
         }
     }
@@ -58,6 +94,7 @@ pub type ServiceResult<T> = Result<T, ServiceError>;
 #[derive(Debug)]
 pub enum DaoError {
     NotFound(String),
+    Conflict(String),
     Unknown(String),
 }
 
@@ -65,9 +102,10 @@ impl fmt::Display for DaoError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match &self {
             DaoError::NotFound(msg) => format!("Not Found: {}", msg),
+            DaoError::Conflict(msg) => format!("Conflict: {}", msg),
             DaoError::Unknown(msg) => format!("Unknown: {}", msg),
         };
-        write!(f, "{}", format!("DAO Error: {}", msg))
+        write!(f, "DAO Error: {}", msg)
     }
 }
 
@@ -75,7 +113,7 @@ impl Error for DaoError {}
 
 pub type DaoResult<T> = Result<T, DaoError>;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize)]
 pub struct UniqueError {
     entity_name: String,
     field_name: String,
@@ -94,10 +132,11 @@ impl UniqueError {
 
 impl fmt::Display for UniqueError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", format!(
+        write!(
+            f,
             "{} values must be unique within a single {}, and the provided value already exists: {}",
             self.field_name, self.entity_name, self.value
-        ))
+        )
     }
 }
 
@@ -107,9 +146,11 @@ pub struct Lowercase {
 }
 
 impl Lowercase {
+    /// Applies NFKC normalization before lowercasing, so that visually identical strings
+    /// (e.g. full-width or composed/decomposed forms) produce the same key.
     pub fn new(value: &str) -> Self {
         Self {
-            value: value.to_lowercase(),
+            value: value.nfkc().collect::<String>().to_lowercase(),
         }
     }
     pub fn value(&self) -> &str {
@@ -163,6 +204,34 @@ impl GameNameBody {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct NewGameBody {
+    pub game_name: String,
+    #[serde(default)]
+    pub settings: GameSettings,
+    /// Words supplied by the host, used instead of the dictionaries in the settings.
+    #[serde(default)]
+    pub custom_words: Option<CustomWords>,
+    /// Makes the board reproducible: the same seed and dictionaries give the same board.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CustomWords {
+    /// Exactly the words for the board.
+    Board { words: Vec<String> },
+    /// A pool to draw the board from, optionally saved under a name for later games.
+    Pool {
+        words: Vec<String>,
+        #[serde(default)]
+        save_as: Option<String>,
+    },
+    /// A pool saved by an earlier game.
+    Saved { name: String },
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GameListBody {
     pub games: Vec<String>,
@@ -174,8 +243,85 @@ impl GameListBody {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct DictionaryListBody {
+    pub dictionaries: Vec<DictionarySummary>,
+}
+
+impl DictionaryListBody {
+    pub fn new(dictionaries: Vec<DictionarySummary>) -> Self {
+        Self { dictionaries }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DictionaryReportBody {
+    pub dictionaries: Vec<DictionaryReport>,
+}
+
+impl DictionaryReportBody {
+    pub fn new(dictionaries: Vec<DictionaryReport>) -> Self {
+        Self { dictionaries }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyGameBody {
+    pub game_name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LeaderboardBody {
+    pub date: NaiveDate,
+    pub results: Vec<DailyResult>,
+}
+
+impl LeaderboardBody {
+    pub fn new(date: NaiveDate, results: Vec<DailyResult>) -> Self {
+        Self { date, results }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ClueBody {
     pub word: String,
     pub amount: usize,
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct ReadyBody {
+    pub ready: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SwitchPlayerBody {
+    pub team: Team,
+    pub spymaster_secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpymasterHandOffBody {
+    pub teammate: String,
+    pub spymaster_secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    pub details: serde_json::Value,
+}
+
+impl ErrorBody {
+    pub fn new(code: &str, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            message,
+            details: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_details(self, details: serde_json::Value) -> Self {
+        Self { details, ..self }
+    }
+}
@@ -36,7 +36,10 @@ def test_get_game_name(host, test_state):
 
 
 def test_create_game(host, test_state):
-    r = requests.post(f"{host}/game", json=vars(test_state))
+    r = requests.post(
+        f"{host}/game",
+        json={"game_name": test_state.game_name, "settings": {"lobby": True}},
+    )
     assert r.status_code == 200
     turns = r.json().get("turns")
     assert len(turns) == 1
@@ -107,8 +110,76 @@ def test_join_game_player_exists(host, test_state):
         },
     )
 
-    assert r.status_code == 400
-    assert r.json().get("msg") is not None
+    assert r.status_code == 409
+    assert r.json().get("code") == "UNIQUE_PLAYER_NAME"
+    assert r.json().get("message") is not None
+
+
+def test_join_game_second_spy_master(host, test_state):
+    r = requests.put(
+        f"{host}/game/{test_state.game_name}/join",
+        json={
+            "name": "Mrs Spy Master",
+            "team": test_state.current_turn,
+            "spymaster_secret": "bar",
+        },
+    )
+
+    assert r.status_code == 409
+    assert r.json().get("code") == "UNIQUE_SPYMASTER"
+
+
+def test_start_turn_in_lobby(host, test_state):
+    r = requests.put(
+        f"{host}/game/{test_state.game_name}/mr spy master/start-turn",
+        json={
+            "word": "foo",
+            "amount": 1,
+        },
+    )
+
+    assert r.status_code == 409
+    assert r.json().get("code") == "GAME_NOT_STARTED"
+
+
+def test_join_other_team(host, test_state):
+    other_team = "Red" if test_state.current_turn == "Blue" else "Blue"
+    for player in [
+        {"name": "Ms Spy Master", "team": other_team, "spymaster_secret": "baz"},
+        {"name": "Ms Operative", "team": other_team},
+    ]:
+        r = requests.put(f"{host}/game/{test_state.game_name}/join", json=player)
+        assert r.status_code == 200
+
+
+def test_start_game_not_ready(host, test_state):
+    r = requests.put(f"{host}/game/{test_state.game_name}/mr operative/start")
+
+    assert r.status_code == 409
+    assert r.json().get("code") == "PLAYERS_NOT_READY"
+
+
+def test_ready(host, test_state):
+    for player in ["mr operative", "mr spy master", "ms spy master", "ms operative"]:
+        r = requests.put(
+            f"{host}/game/{test_state.game_name}/{player}/ready",
+            json={"ready": True},
+        )
+        assert r.status_code == 200
+
+
+def test_start_game_not_host(host, test_state):
+    r = requests.put(f"{host}/game/{test_state.game_name}/mr spy master/start")
+
+    assert r.status_code == 403
+    assert r.json().get("code") == "NOT_THE_HOST"
+
+
+def test_start_game(host, test_state):
+    r = requests.put(f"{host}/game/{test_state.game_name}/mr operative/start")
+
+    assert r.status_code == 200
+    assert r.json().get("phase").get("type") == "Started"
 
 
 def test_start_turn(host, test_state):
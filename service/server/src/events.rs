@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use codenames_common::{
+    game::{model::GameData, observer::GameObserver},
+    Lowercase,
+};
+use tokio::sync::broadcast;
+
+/// How many unread updates a slow subscriber may fall behind before it starts missing them.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// A broadcast channel per game, fed by `GameService` as games are saved.
+#[derive(Clone, Default)]
+pub struct GameEvents {
+    channels: Arc<Mutex<HashMap<Lowercase, broadcast::Sender<GameData>>>>,
+}
+
+impl GameEvents {
+    pub fn new() -> GameEvents {
+        GameEvents::default()
+    }
+
+    pub fn subscribe(&self, key: &str) -> broadcast::Receiver<GameData> {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(Lowercase::new(key))
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+}
+
+impl GameObserver for GameEvents {
+    fn game_saved(&self, game: &GameData) {
+        let mut channels = self.channels.lock().unwrap();
+        let key = Lowercase::new(game.info.name());
+        if let Some(sender) = channels.get(&key) {
+            // an error only means nobody is listening any more
+            if sender.send(game.clone()).is_err() {
+                channels.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codenames_common::game::{
+        model::{Card, CardColor, GameData, GameSettings, Team},
+        observer::GameObserver,
+    };
+
+    use crate::events::GameEvents;
+
+    fn test_game(name: &str) -> GameData {
+        let board = std::array::from_fn(|i| Card {
+            color: CardColor::Neutral,
+            word: i.to_string(),
+        });
+        GameData::new(name.to_string(), board, Team::Blue, GameSettings::default())
+    }
+
+    #[test]
+    fn game_saved() {
+        let events = GameEvents::new();
+        let mut receiver = events.subscribe("FOO");
+        let game = test_game("foo");
+        events.game_saved(&test_game("bar"));
+        events.game_saved(&game);
+        assert_eq!(receiver.try_recv().unwrap(), game);
+        assert!(receiver.try_recv().is_err());
+    }
+}
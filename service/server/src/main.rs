@@ -0,0 +1,86 @@
+use std::env;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    response::Response,
+    routing::get,
+    Router,
+};
+use codenames_api::{router, service_error_response};
+use codenames_common::{
+    game::{
+        dao::{DynamoDao, GameDao, MemoryDao},
+        service::GameService,
+    },
+    ServiceError, StdResult,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::events::GameEvents;
+
+mod events;
+mod sse;
+mod ws;
+
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Clone)]
+pub struct AppState {
+    service: GameService,
+    events: GameEvents,
+}
+
+#[derive(Deserialize)]
+pub struct ViewerQuery {
+    secret: Option<String>,
+}
+
+/// Chooses the `GameDao` from the `CODENAMES_DAO` environment variable: `dynamo` (the default)
+/// or `memory`.
+async fn dao_from_env() -> StdResult<Box<dyn GameDao + Send + Sync>> {
+    match env::var("CODENAMES_DAO")
+        .unwrap_or_else(|_| "dynamo".to_string())
+        .as_str()
+    {
+        "dynamo" => Ok(Box::new(DynamoDao::new().await?)),
+        "memory" => Ok(Box::new(MemoryDao::new())),
+        other => Err(format!("unknown CODENAMES_DAO: {}", other).into()),
+    }
+}
+
+async fn handle(State(AppState { service, .. }): State<AppState>, req: Request) -> Response {
+    let (parts, body) = req.into_parts();
+    let response = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => router(Request::from_parts(parts, bytes.to_vec()), service).await,
+        Err(e) => service_error_response(&ServiceError::BadRequest(e.to_string())),
+    };
+    response.map(Body::from)
+}
+
+#[tokio::main]
+async fn main() -> StdResult<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let events = GameEvents::new();
+    let service = GameService::from_env(dao_from_env().await?)
+        .await?
+        .with_observer(Box::new(events.clone()));
+
+    let port: u16 = env::var("PORT")
+        .unwrap_or_else(|_| "8080".to_string())
+        .parse()?;
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("listening on {}", listener.local_addr()?);
+
+    let app = Router::new()
+        .route("/game/{id}/{player}/events", get(sse::player_events))
+        .route("/game/{id}/{player}/ws", get(ws::player_socket))
+        .fallback(handle)
+        .with_state(AppState { service, events });
+    axum::serve(listener, app).await?;
+    Ok(())
+}
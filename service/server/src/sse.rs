@@ -0,0 +1,51 @@
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use codenames_api::service_error_response;
+use codenames_common::game::service::GameService;
+use tokio_stream::{once, wrappers::BroadcastStream, StreamExt};
+
+use crate::{AppState, ViewerQuery};
+
+/// `GET /game/{id}/{player}/events` streams the game each time it changes, projected for the
+/// viewer in the same way as `GET /game/{id}/{player}`. The first event is the current state.
+pub async fn player_events(
+    State(AppState { service, events }): State<AppState>,
+    Path((id, player_name)): Path<(String, String)>,
+    Query(ViewerQuery { secret }): Query<ViewerQuery>,
+) -> Response {
+    // subscribe before reading, so no update can slip in between
+    let updates = events.subscribe(&id);
+    let player_name = Some(player_name);
+
+    let current = match service.clone().get(&id, &player_name, &secret).await {
+        Ok(game) => game,
+        Err(e) => return service_error_response(&e).into_response(),
+    };
+
+    let updates = BroadcastStream::new(updates)
+        // a lagging subscriber skips to the next update, which carries the full game anyway
+        .filter_map(|update| update.ok())
+        .map(move |data| GameService::view(data, &player_name, &secret))
+        // the viewer has left the game
+        .take_while(|view| view.is_ok())
+        .filter_map(|view| view.ok());
+
+    let stream = once(current).chain(updates).map(to_event);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+        .into_response()
+}
+
+fn to_event<T: serde::Serialize>(game: T) -> Result<Event, Infallible> {
+    Ok(Event::default()
+        .event("game")
+        .json_data(game)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string())))
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    response::Response,
+};
+use codenames_api::socket::{handle_message, ServerMessage};
+use codenames_common::{
+    game::{
+        model::{Game, GameData},
+        service::GameService,
+    },
+    ServiceError, ServiceResult,
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::{AppState, ViewerQuery};
+
+/// `GET /game/{id}/{player}/ws` opens a socket on which the player sends commands and receives
+/// acknowledgements, errors, and the game (projected for them) each time it changes.
+pub async fn player_socket(
+    ws: WebSocketUpgrade,
+    State(AppState { service, events }): State<AppState>,
+    Path((id, player_name)): Path<(String, String)>,
+    Query(ViewerQuery { secret }): Query<ViewerQuery>,
+) -> Response {
+    ws.on_upgrade(move |socket| async move {
+        let updates = events.subscribe(&id);
+        let viewer = Viewer {
+            service,
+            id,
+            player_name,
+            secret,
+        };
+        viewer.play(socket, updates).await
+    })
+}
+
+struct Viewer {
+    service: GameService,
+    id: String,
+    player_name: String,
+    secret: Option<String>,
+}
+
+impl Viewer {
+    async fn play(&self, mut socket: WebSocket, mut updates: Receiver<GameData>) {
+        let current = match self.current().await {
+            Ok(game) => ServerMessage::Game {
+                game: Box::new(game),
+            },
+            Err(e) => {
+                let error = ServerMessage::Error {
+                    id: None,
+                    error: e.body(),
+                };
+                let _ = send(&mut socket, &error).await;
+                return;
+            }
+        };
+        if send(&mut socket, &current).await.is_err() {
+            return;
+        }
+
+        loop {
+            let message = tokio::select! {
+                received = socket.recv() => match received {
+                    Some(Ok(Message::Text(text))) => {
+                        handle_message(&self.service, &self.id, &self.player_name, &text).await
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+                update = updates.recv() => match update {
+                    Ok(data) => ServerMessage::Game { game: Box::new(self.project(data)) },
+                    // the next update carries the full game anyway
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if send(&mut socket, &message).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Until the player has joined, they see what any anonymous viewer would.
+    async fn current(&self) -> ServiceResult<Game> {
+        let player_name = Some(self.player_name.clone());
+        match self
+            .service
+            .clone()
+            .get(&self.id, &player_name, &self.secret)
+            .await
+        {
+            Err(ServiceError::NotFound(_)) => {
+                self.service.clone().get(&self.id, &None, &None).await
+            }
+            result => result,
+        }
+    }
+
+    fn project(&self, data: GameData) -> Game {
+        let player_name = Some(self.player_name.clone());
+        GameService::view(data.clone(), &player_name, &self.secret)
+            .unwrap_or_else(|_| Game::State(data.into()))
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("server messages always serialize");
+    socket.send(Message::Text(text.into())).await
+}